@@ -1,60 +1,112 @@
 use tauri::AppHandle;
 use crate::db;
-use crate::models::{ApiKey, CreateApiKeyRequest, UpdateApiKeyRequest, ConfigItem, ApiKeyData};
+use crate::crypto;
+use crate::commands::backup;
+use crate::commands::router;
+use crate::commands::config::{expand_home_path, write_env_to_settings};
+use std::fs;
+use std::path::Path;
+use crate::models::{ApiKey, CreateApiKeyRequest, UpdateApiKeyRequest, ConfigItem, ApiKeyData, ApiKeyDriftReport, ApiKeyHistoryEntry};
 use rusqlite::OptionalExtension;
 use chrono;
 use uuid;
 
+/// How long a rotated-out key stays recoverable with `revert_api_key`.
+const KEY_HISTORY_RETENTION_DAYS: i64 = 7;
+
+pub const API_KEY_COLUMNS: &str = "id, name, ANTHROPIC_API_KEY, description, ANTHROPIC_BASE_URL, is_active, expires_at, tags, owner, last_used_at, created_at, updated_at";
+
+pub fn row_to_api_key(row: &rusqlite::Row) -> rusqlite::Result<ApiKey> {
+    let tags_json: Option<String> = row.get(7)?;
+    Ok(ApiKey {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        anthropic_api_key: row.get(2)?,
+        description: row.get(3)?,
+        anthropic_base_url: row.get(4)?,
+        is_active: row.get(5)?,
+        expires_at: row.get(6)?,
+        tags: tags_json.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default(),
+        owner: row.get(8)?,
+        last_used_at: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+/// Accepts either a full RFC 3339 timestamp or a plain `YYYY-MM-DD` date (end of that day, UTC).
+fn parse_expiry(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|datetime| datetime.and_utc())
+}
+
+fn validate_expiry(expires_at: &Option<String>) -> Result<(), String> {
+    match expires_at {
+        Some(value) if parse_expiry(value).is_none() => Err(format!("Invalid expiry date '{}'", value)),
+        _ => Ok(()),
+    }
+}
+
+/// The key as the frontend sees it: masked, never the stored ciphertext. `reveal_api_key`
+/// returns the plaintext on request.
+fn masked(mut api_key: ApiKey) -> Result<ApiKey, String> {
+    api_key.anthropic_api_key = crypto::mask_secret(&crypto::decrypt_secret(&api_key.anthropic_api_key)?);
+    Ok(api_key)
+}
+
+fn tags_to_json(tags: &[String]) -> String {
+    serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
+
 #[tauri::command]
 pub async fn create_api_key(
     app: AppHandle,
     request: CreateApiKeyRequest,
 ) -> Result<ApiKey, String> {
+    validate_expiry(&request.expires_at)?;
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
 
     let now = chrono::Utc::now().to_rfc3339();
     let api_key = ApiKey {
         id: uuid::Uuid::new_v4().to_string(),
         name: request.name,
-        anthropic_api_key: request.anthropic_api_key,
+        anthropic_api_key: crypto::encrypt_secret(&request.anthropic_api_key)?,
         description: request.description,
         anthropic_base_url: request.anthropic_base_url,
         is_active: true,
+        expires_at: request.expires_at,
+        tags: request.tags.unwrap_or_default(),
+        owner: request.owner,
+        last_used_at: None,
         created_at: now.clone(),
         updated_at: now,
     };
 
     conn.execute(
-        "INSERT INTO api_keys (id, name, ANTHROPIC_API_KEY, description, ANTHROPIC_BASE_URL, is_active, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        (&api_key.id, &api_key.name, &api_key.anthropic_api_key, &api_key.description, &api_key.anthropic_base_url, &api_key.is_active, &api_key.created_at, &api_key.updated_at),
+        "INSERT INTO api_keys (id, name, ANTHROPIC_API_KEY, description, ANTHROPIC_BASE_URL, is_active, expires_at, tags, owner, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        (&api_key.id, &api_key.name, &api_key.anthropic_api_key, &api_key.description, &api_key.anthropic_base_url, &api_key.is_active, &api_key.expires_at, tags_to_json(&api_key.tags), &api_key.owner, &api_key.created_at, &api_key.updated_at),
     ).map_err(|e| e.to_string())?;
 
-    Ok(api_key)
+    masked(api_key)
 }
 
 #[tauri::command]
 pub async fn get_api_keys(app: AppHandle) -> Result<Vec<ApiKey>, String> {
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
 
-    let mut stmt = conn.prepare("SELECT id, name, ANTHROPIC_API_KEY, description, ANTHROPIC_BASE_URL, is_active, created_at, updated_at FROM api_keys ORDER BY is_active DESC, created_at DESC")
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM api_keys ORDER BY is_active DESC, created_at DESC", API_KEY_COLUMNS))
         .map_err(|e| e.to_string())?;
     
-    let api_keys = stmt.query_map([], |row| {
-        Ok(ApiKey {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            anthropic_api_key: row.get(2)?,
-            description: row.get(3)?,
-            anthropic_base_url: row.get(4)?,
-            is_active: row.get(5)?,
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    let api_keys = stmt.query_map([], row_to_api_key).map_err(|e| e.to_string())?;
 
     let mut result = Vec::new();
     for api_key in api_keys {
-        result.push(api_key.map_err(|e| e.to_string())?);
+        result.push(masked(api_key.map_err(|e| e.to_string())?)?);
     }
 
     Ok(result)
@@ -81,7 +133,7 @@ pub async fn get_api_keys_config(app: AppHandle) -> Result<Vec<ConfigItem<ApiKey
             id,
             name,
             data: ApiKeyData {
-                anthropic_api_key: anthropic_api_key,
+                anthropic_api_key,
                 anthropic_base_url: anthropic_base_url,
             },
             description,
@@ -93,82 +145,741 @@ pub async fn get_api_keys_config(app: AppHandle) -> Result<Vec<ConfigItem<ApiKey
 
     let mut result = Vec::new();
     for api_key in api_keys {
-        result.push(api_key.map_err(|e| e.to_string())?);
+        let mut item = api_key.map_err(|e| e.to_string())?;
+        item.data.anthropic_api_key = crypto::mask_secret(&crypto::decrypt_secret(&item.data.anthropic_api_key)?);
+        result.push(item);
     }
 
     Ok(result)
 }
 
+/// Updates a key's details. A new `ANTHROPIC_API_KEY` is applied as a rotation, so the previous
+/// value goes to the history and the settings file is updated when it uses this key. The masked
+/// value the list commands return, or the stored ciphertext, counts as unchanged.
 #[tauri::command]
 pub async fn update_api_key(
     app: AppHandle,
     id: String,
     request: UpdateApiKeyRequest,
 ) -> Result<ApiKey, String> {
+    // 空字符串表示清除到期时间
+    let expires_at = request.expires_at.map(|e| Some(e).filter(|e| !e.is_empty()));
+    if let Some(expires_at) = &expires_at {
+        validate_expiry(expires_at)?;
+    }
+    let new_key = request.anthropic_api_key.map(|k| k.trim().to_string());
+    if new_key.as_deref() == Some("") {
+        return Err("API key must not be empty".to_string());
+    }
+    let settings_file = settings_file_path(&app).await?;
+
+    let (api_key, key_changed) = {
+        let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        purge_expired_history(&conn)?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        let mut api_key = tx.query_row(
+            &format!("SELECT {} FROM api_keys WHERE id = ?1", API_KEY_COLUMNS),
+            [&id],
+            row_to_api_key,
+        ).optional().map_err(|e| e.to_string())?
+        .ok_or("API key not found")?;
+
+        if let Some(name) = request.name {
+            api_key.name = name;
+        }
+        if let Some(description) = request.description {
+            api_key.description = Some(description);
+        }
+        if let Some(anthropic_base_url) = request.anthropic_base_url {
+            api_key.anthropic_base_url = Some(anthropic_base_url);
+        }
+        if let Some(expires_at) = expires_at {
+            api_key.expires_at = expires_at;
+        }
+        if let Some(tags) = request.tags {
+            api_key.tags = tags;
+        }
+        if let Some(owner) = request.owner {
+            api_key.owner = Some(owner).filter(|o| !o.is_empty());
+        }
+        api_key.updated_at = chrono::Utc::now().to_rfc3339();
+
+        tx.execute(
+            "UPDATE api_keys SET name = ?1, description = ?2, ANTHROPIC_BASE_URL = ?3, expires_at = ?4, tags = ?5, owner = ?6, updated_at = ?7 WHERE id = ?8",
+            (&api_key.name, &api_key.description, &api_key.anthropic_base_url, &api_key.expires_at, tags_to_json(&api_key.tags), &api_key.owner, &api_key.updated_at, &id),
+        ).map_err(|e| e.to_string())?;
+
+        // 密钥本身的变更走轮换流程，和元数据在同一个事务里提交
+        match new_key.filter(|k| !crypto::is_encrypted(k)) {
+            Some(new_key) if is_new_key(&new_key, &crypto::decrypt_secret(&api_key.anthropic_api_key)?) => {
+                (replace_key(tx, &id, &settings_file, crypto::encrypt_secret(&new_key)?, "rotate", None)?, true)
+            }
+            _ => {
+                tx.commit().map_err(|e| e.to_string())?;
+                (api_key, false)
+            }
+        }
+    };
+
+    // 引用了这个密钥的 Router 提供商需要重新导出
+    if key_changed {
+        router::export_providers_using_key(&app, &id).await?;
+    }
+
+    masked(api_key)
+}
+
+// 前端编辑时原样提交的脱敏值不是新密钥
+fn is_new_key(incoming: &str, current: &str) -> bool {
+    incoming != current && incoming != crypto::mask_secret(current)
+}
+
+/// Router providers that use the stored key `id`, both in the live config and in saved
+/// router profiles (listed as `profile/provider`).
+fn key_references(conn: &rusqlite::Connection, id: &str) -> Result<Vec<String>, String> {
+    let mut referenced_by: Vec<String> = conn.prepare("SELECT name FROM providers WHERE api_key_id = ?1")
+        .and_then(|mut stmt| stmt.query_map([id], |row| row.get(0))?.collect())
+        .map_err(|e| e.to_string())?;
+
+    // 已保存的配置方案激活时会写回 providers 表，所以其中的引用也要算上
+    let profiles: Vec<(String, String)> = conn.prepare("SELECT name, config FROM router_profiles ORDER BY name")
+        .and_then(|mut stmt| stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect())
+        .map_err(|e| e.to_string())?;
+    for (profile_name, config_json) in profiles {
+        let config = crate::models::ClaudeCodeRouterConfig::from_json_str(&config_json)
+            .map_err(|e| format!("Failed to read router profile '{}': {}", profile_name, e))?;
+        referenced_by.extend(config.providers.iter()
+            .filter(|p| p.api_key_id.as_deref() == Some(id))
+            .map(|p| format!("{}/{}", profile_name, p.name)));
+    }
+
+    Ok(referenced_by)
+}
+
+// 检查引用和删除放在同一个写事务里，检查之后不会有新的引用插进来
+fn delete_key(conn: &mut rusqlite::Connection, id: &str) -> Result<bool, String> {
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+
+    let referenced_by = key_references(&tx, id)?;
+    if !referenced_by.is_empty() {
+        return Err(format!("API key is still used by router providers: {}", referenced_by.join(", ")));
+    }
+
+    let affected_rows = tx.execute(
+        "DELETE FROM api_keys WHERE id = ?1",
+        [id],
+    ).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM api_key_history WHERE api_key_id = ?1", [id]).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(affected_rows > 0)
+}
+
+#[tauri::command]
+pub async fn delete_api_key(app: AppHandle, id: String) -> Result<bool, String> {
+    let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    delete_key(&mut conn, &id)
+}
+
+#[tauri::command]
+pub async fn toggle_api_key_active(app: AppHandle, id: String) -> Result<bool, String> {
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+
+    let affected_rows = conn.execute(
+        "UPDATE api_keys SET is_active = NOT is_active, updated_at = ?1 WHERE id = ?2",
+        (chrono::Utc::now().to_rfc3339(), &id),
+    ).map_err(|e| e.to_string())?;
+
+    Ok(affected_rows > 0)
+}
+
+#[tauri::command]
+pub async fn reveal_api_key(app: AppHandle, id: String) -> Result<String, String> {
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
 
-    // Check if the API key exists
-    let existing_key: Option<ApiKey> = conn.query_row(
-        "SELECT id, name, ANTHROPIC_API_KEY, description, ANTHROPIC_BASE_URL, is_active, created_at, updated_at FROM api_keys WHERE id = ?1",
+    let stored: Option<String> = conn.query_row(
+        "SELECT ANTHROPIC_API_KEY FROM api_keys WHERE id = ?1",
         [&id],
-        |row| {
-            Ok(ApiKey {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                anthropic_api_key: row.get(2)?,
-                description: row.get(3)?,
-                anthropic_base_url: row.get(4)?,
-                is_active: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        },
+        |row| row.get(0),
     ).optional().map_err(|e| e.to_string())?;
 
-    let mut api_key = existing_key.ok_or("API key not found")?;
+    crypto::decrypt_secret(&stored.ok_or("API key not found")?)
+}
+
+/// Makes `id` the only active key and writes it into the configured settings file.
+///
+/// The previous settings file is backed up first. If writing the file or committing the
+/// database changes fails, the file is restored and nothing is committed.
+#[tauri::command]
+pub async fn activate_api_key(app: AppHandle, id: String) -> Result<ApiKey, String> {
+    let config_path = super::config_path::get_config_path(app.clone()).await?;
+    let settings_file = expand_home_path(&config_path)?;
+
+    let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut api_key: ApiKey = tx.query_row(
+        &format!("SELECT {} FROM api_keys WHERE id = ?1", API_KEY_COLUMNS),
+        [&id],
+        row_to_api_key,
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or("API key not found")?;
+
+    api_key.is_active = true;
+    api_key.updated_at = chrono::Utc::now().to_rfc3339();
+
+    tx.execute(
+        "UPDATE api_keys SET is_active = (id = ?1), updated_at = CASE WHEN id = ?1 THEN ?2 ELSE updated_at END",
+        (&id, &api_key.updated_at),
+    ).map_err(|e| e.to_string())?;
+
+    apply_key_to_settings(tx, &settings_file, &mut api_key)
+        .map_err(|e| format!("Failed to activate API key, changes rolled back: {}", e))?;
+
+    masked(api_key)
+}
+
+/// Backs up the settings file into `tx`, writes `api_key` into it and commits. If any step
+/// fails the file is restored and the transaction is dropped without committing.
+fn apply_key_to_settings(tx: rusqlite::Transaction, settings_file: &Path, api_key: &mut ApiKey) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    tx.execute("UPDATE api_keys SET last_used_at = ?1 WHERE id = ?2", (&now, &api_key.id))
+        .map_err(|e| e.to_string())?;
+    api_key.last_used_at = Some(now);
 
-    if let Some(name) = request.name {
-        api_key.name = name;
+    let previous_content = if settings_file.exists() {
+        Some(fs::read_to_string(settings_file).map_err(|e| format!("Failed to read settings file: {}", e))?)
+    } else {
+        None
+    };
+    if let Some(content) = &previous_content {
+        backup::insert_backup(&tx, &backup::auto_backup_filename(), content)?;
     }
-    if let Some(anthropic_api_key) = request.anthropic_api_key {
-        api_key.anthropic_api_key = anthropic_api_key;
+
+    let plaintext_key = crypto::decrypt_secret(&api_key.anthropic_api_key)?;
+    let result = write_env_to_settings(settings_file, &plaintext_key, api_key.anthropic_base_url.clone())
+        .and_then(|_| tx.commit().map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        restore_settings_file(settings_file, previous_content.as_deref());
+        return Err(e);
     }
-    if let Some(description) = request.description {
-        api_key.description = Some(description);
+
+    Ok(())
+}
+
+fn restore_settings_file(settings_file: &Path, previous_content: Option<&str>) {
+    let restored = match previous_content {
+        Some(content) => fs::write(settings_file, content),
+        None if settings_file.exists() => fs::remove_file(settings_file),
+        None => Ok(()),
+    };
+    if let Err(e) = restored {
+        eprintln!("Failed to restore settings file {:?}: {}", settings_file, e);
     }
-    if let Some(anthropic_base_url) = request.anthropic_base_url {
-        api_key.anthropic_base_url = Some(anthropic_base_url);
+}
+
+fn settings_env(settings_file: &Path) -> Result<serde_json::Value, String> {
+    if !settings_file.exists() {
+        return Ok(serde_json::Value::Null);
     }
-    api_key.updated_at = chrono::Utc::now().to_rfc3339();
+    let content = fs::read_to_string(settings_file)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let settings: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings file: {}", e))?;
+    Ok(settings.get("env").cloned().unwrap_or(serde_json::Value::Null))
+}
+
+fn env_api_key(env: &serde_json::Value) -> Option<String> {
+    // update_config_env writes both variables, but hand-edited files may only have one
+    env.get("ANTHROPIC_API_KEY")
+        .or_else(|| env.get("ANTHROPIC_AUTH_TOKEN"))
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+/// The key currently set in the settings file, if any.
+fn settings_api_key(settings_file: &Path) -> Result<Option<String>, String> {
+    Ok(env_api_key(&settings_env(settings_file)?))
+}
+
+/// A stored key with its secret decrypted, as compared against the settings file.
+struct StoredKey {
+    id: String,
+    name: String,
+    api_key: String,
+    base_url: Option<String>,
+    is_active: bool,
+}
+
+/// Compares the key and base URL of a settings file's `env` block with the stored keys.
+fn compare_with_settings(settings_path: &str, env: &serde_json::Value, stored_keys: &[StoredKey]) -> ApiKeyDriftReport {
+    let disk_api_key = env_api_key(env);
+    let disk_base_url = env.get("ANTHROPIC_BASE_URL")
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+
+    let active_key_ids = stored_keys.iter()
+        .filter(|key| key.is_active)
+        .map(|key| key.id.clone())
+        .collect();
+    let matched = disk_api_key.as_ref()
+        .and_then(|disk_key| stored_keys.iter().find(|key| key.api_key == *disk_key));
+
+    let normalize = |url: Option<&str>| url.map(|u| u.trim_end_matches('/').to_string()).filter(|u| !u.is_empty());
+    let base_url_matches = matched
+        .is_some_and(|key| normalize(key.base_url.as_deref()) == normalize(disk_base_url.as_deref()));
+
+    let status = match (&disk_api_key, matched) {
+        (None, _) => "no_key",
+        (Some(_), None) => "unknown_key",
+        (Some(_), Some(key)) if key.is_active && base_url_matches => "in_sync",
+        (Some(_), Some(_)) => "drift",
+    };
+
+    ApiKeyDriftReport {
+        settings_path: settings_path.to_string(),
+        status: status.to_string(),
+        disk_api_key: disk_api_key.as_deref().map(crypto::mask_secret),
+        disk_base_url,
+        matched_key_id: matched.map(|key| key.id.clone()),
+        matched_key_name: matched.map(|key| key.name.clone()),
+        base_url_matches,
+        active_key_ids,
+    }
+}
 
+/// Compares the key and base URL in the configured settings file with the stored keys.
+#[tauri::command]
+pub async fn detect_api_key_drift(app: AppHandle) -> Result<ApiKeyDriftReport, String> {
+    let config_path = super::config_path::get_config_path(app.clone()).await?;
+    let settings_file = expand_home_path(&config_path)?;
+    let env = settings_env(&settings_file)?;
+
+    let stored_keys: Vec<StoredKey> = {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare("SELECT id, name, ANTHROPIC_API_KEY, ANTHROPIC_BASE_URL, is_active FROM api_keys")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok(StoredKey {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            api_key: row.get(2)?,
+            base_url: row.get(3)?,
+            is_active: row.get(4)?,
+        })).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let stored_keys = stored_keys.into_iter()
+        .map(|key| Ok(StoredKey { api_key: crypto::decrypt_secret(&key.api_key)?, ..key }))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(compare_with_settings(&settings_file.to_string_lossy(), &env, &stored_keys))
+}
+
+fn history_cutoff() -> String {
+    (chrono::Utc::now() - chrono::Duration::days(KEY_HISTORY_RETENTION_DAYS)).to_rfc3339()
+}
+
+// 超过保留期的历史记录只保留脱敏值，密文不再保存
+fn purge_expired_history(conn: &rusqlite::Connection) -> Result<(), String> {
     conn.execute(
-        "UPDATE api_keys SET name = ?1, ANTHROPIC_API_KEY = ?2, description = ?3, ANTHROPIC_BASE_URL = ?4, is_active = ?5, updated_at = ?6 WHERE id = ?7",
-        (&api_key.name, &api_key.anthropic_api_key, &api_key.description, &api_key.anthropic_base_url, &api_key.is_active, &api_key.updated_at, &id),
+        "UPDATE api_key_history SET encrypted_key = NULL WHERE encrypted_key IS NOT NULL AND rotated_at < ?1",
+        [history_cutoff()],
     ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Swaps in `new_key` (already encrypted), recording the current value in the history, and
+/// commits `tx`. When the key is the one applied to Claude Code, the settings file is updated
+/// in the same step.
+fn replace_key(
+    tx: rusqlite::Transaction,
+    id: &str,
+    settings_file: &Path,
+    new_key: String,
+    reason: &str,
+    restored_entry: Option<i64>,
+) -> Result<ApiKey, String> {
+
+    let mut api_key = tx.query_row(
+        &format!("SELECT {} FROM api_keys WHERE id = ?1", API_KEY_COLUMNS),
+        [id],
+        row_to_api_key,
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or("API key not found")?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let previous_key = crypto::decrypt_secret(&api_key.anthropic_api_key)?;
+    tx.execute(
+        "INSERT INTO api_key_history (api_key_id, masked_key, encrypted_key, reason, rotated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        (id, crypto::mask_secret(&previous_key), &api_key.anthropic_api_key, reason, &now),
+    ).map_err(|e| e.to_string())?;
+    if let Some(entry_id) = restored_entry {
+        tx.execute("UPDATE api_key_history SET reverted_at = ?1 WHERE id = ?2", (&now, entry_id))
+            .map_err(|e| e.to_string())?;
+    }
+
+    api_key.anthropic_api_key = new_key;
+    api_key.updated_at = now;
+    tx.execute(
+        "UPDATE api_keys SET ANTHROPIC_API_KEY = ?1, updated_at = ?2 WHERE id = ?3",
+        (&api_key.anthropic_api_key, &api_key.updated_at, id),
+    ).map_err(|e| e.to_string())?;
+
+    // 旧数据里可能同时有多个 is_active 的密钥，这时无法从启用状态判断 settings.json 用的是哪一个，
+    // 所以只在文件里的密钥正是被替换的旧值时才更新它
+    let active_count: i64 = tx.query_row("SELECT COUNT(*) FROM api_keys WHERE is_active = 1", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let applied = if active_count == 1 {
+        api_key.is_active
+    } else {
+        settings_api_key(settings_file)?.as_deref() == Some(previous_key.as_str())
+    };
+    if applied {
+        apply_key_to_settings(tx, settings_file, &mut api_key)
+            .map_err(|e| format!("Failed to apply rotated key, changes rolled back: {}", e))?;
+    } else {
+        tx.commit().map_err(|e| e.to_string())?;
+    }
 
     Ok(api_key)
 }
 
+async fn settings_file_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+    let config_path = super::config_path::get_config_path(app.clone()).await?;
+    expand_home_path(&config_path)
+}
+
+/// Replaces a key with `new_key`, keeping the previous value for `KEY_HISTORY_RETENTION_DAYS`.
 #[tauri::command]
-pub async fn delete_api_key(app: AppHandle, id: String) -> Result<bool, String> {
+pub async fn rotate_api_key(app: AppHandle, id: String, new_key: String) -> Result<ApiKey, String> {
+    if new_key.trim().is_empty() {
+        return Err("New API key must not be empty".to_string());
+    }
+
+    let settings_file = settings_file_path(&app).await?;
+    let api_key = {
+        let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        purge_expired_history(&conn)?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        replace_key(tx, &id, &settings_file, crypto::encrypt_secret(new_key.trim())?, "rotate", None)?
+    };
+    router::export_providers_using_key(&app, &id).await?;
+
+    masked(api_key)
+}
+
+/// Restores the key that the most recent rotation replaced, if it is still within the retention window.
+#[tauri::command]
+pub async fn revert_api_key(app: AppHandle, id: String) -> Result<ApiKey, String> {
+    let settings_file = settings_file_path(&app).await?;
+
+    let api_key = {
+        let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        purge_expired_history(&conn)?;
+        let (entry_id, previous_key): (i64, String) = conn.query_row(
+            "SELECT id, encrypted_key FROM api_key_history
+             WHERE api_key_id = ?1 AND reason = 'rotate' AND reverted_at IS NULL AND encrypted_key IS NOT NULL
+             ORDER BY id DESC LIMIT 1",
+            [&id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No previous key within the {}-day retention window", KEY_HISTORY_RETENTION_DAYS))?;
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        replace_key(tx, &id, &settings_file, previous_key, "revert", Some(entry_id))?
+    };
+    router::export_providers_using_key(&app, &id).await?;
+
+    masked(api_key)
+}
+
+#[tauri::command]
+pub async fn get_api_key_history(app: AppHandle, id: String) -> Result<Vec<ApiKeyHistoryEntry>, String> {
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    purge_expired_history(&conn)?;
 
-    let affected_rows = conn.execute(
-        "DELETE FROM api_keys WHERE id = ?1",
-        [&id],
+    let mut stmt = conn.prepare(
+        "SELECT id, api_key_id, masked_key, reason, rotated_at, reverted_at, encrypted_key IS NOT NULL
+         FROM api_key_history WHERE api_key_id = ?1 ORDER BY id DESC",
     ).map_err(|e| e.to_string())?;
 
-    Ok(affected_rows > 0)
+    let entries = stmt.query_map([&id], |row| {
+        let reason: String = row.get(3)?;
+        let reverted_at: Option<String> = row.get(5)?;
+        let has_key: bool = row.get(6)?;
+        Ok(ApiKeyHistoryEntry {
+            id: row.get(0)?,
+            api_key_id: row.get(1)?,
+            masked_key: row.get(2)?,
+            revertible: has_key && reason == "rotate" && reverted_at.is_none(),
+            reason,
+            rotated_at: row.get(4)?,
+            reverted_at,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for entry in entries {
+        result.push(entry.map_err(|e| e.to_string())?);
+    }
+
+    Ok(result)
 }
 
+/// Keys whose `expires_at` is at or before `now + days`, soonest first, still encrypted.
+fn expiring_keys(conn: &rusqlite::Connection, days: i64, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<ApiKey>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM api_keys WHERE expires_at IS NOT NULL", API_KEY_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let api_keys = stmt.query_map([], row_to_api_key).map_err(|e| e.to_string())?;
+
+    let deadline = now + chrono::Duration::days(days.max(0));
+    let mut expiring = Vec::new();
+    for api_key in api_keys {
+        let api_key = api_key.map_err(|e| e.to_string())?;
+        if let Some(expires) = api_key.expires_at.as_deref().and_then(parse_expiry) {
+            if expires <= deadline {
+                expiring.push((expires, api_key));
+            }
+        }
+    }
+    expiring.sort_by_key(|(expires, _)| *expires);
+
+    Ok(expiring.into_iter().map(|(_, api_key)| api_key).collect())
+}
+
+/// Keys whose `expires_at` falls within the next `days` days, including already expired ones,
+/// soonest first.
 #[tauri::command]
-pub async fn toggle_api_key_active(app: AppHandle, id: String) -> Result<bool, String> {
+pub async fn get_expiring_api_keys(app: AppHandle, days: i64) -> Result<Vec<ApiKey>, String> {
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    expiring_keys(&conn, days, chrono::Utc::now())?.into_iter().map(masked).collect()
+}
 
-    let affected_rows = conn.execute(
-        "UPDATE api_keys SET is_active = NOT is_active, updated_at = ?1 WHERE id = ?2",
-        (chrono::Utc::now().to_rfc3339(), &id),
-    ).map_err(|e| e.to_string())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(affected_rows > 0)
+    fn insert_key(conn: &rusqlite::Connection, id: &str, key: &str, is_active: bool) {
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO api_keys (id, name, ANTHROPIC_API_KEY, is_active, created_at, updated_at) VALUES (?1, ?1, ?2, ?3, ?4, ?4)",
+            (id, crypto::encrypt_secret(key).unwrap(), is_active, &now),
+        ).unwrap();
+    }
+
+    fn temp_settings(key: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("claude-meta-api-keys-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let settings_file = dir.join("settings.json");
+        write_env_to_settings(&settings_file, key, None).unwrap();
+        settings_file
+    }
+
+    #[test]
+    fn rotation_records_history_and_updates_the_applied_key() {
+        let mut conn = db::open_test_connection();
+        insert_key(&conn, "a", "sk-old", true);
+        let settings_file = temp_settings("sk-old");
+
+        let tx = conn.transaction().unwrap();
+        replace_key(tx, "a", &settings_file, crypto::encrypt_secret("sk-new").unwrap(), "rotate", None).unwrap();
+
+        assert_eq!(settings_api_key(&settings_file).unwrap().as_deref(), Some("sk-new"));
+        let masked: String = conn.query_row("SELECT masked_key FROM api_key_history WHERE api_key_id = 'a'", [], |row| row.get(0)).unwrap();
+        assert_eq!(masked, crypto::mask_secret("sk-old"));
+        fs::remove_dir_all(settings_file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn with_several_active_keys_only_the_key_in_the_settings_file_is_applied() {
+        let mut conn = db::open_test_connection();
+        insert_key(&conn, "a", "sk-a", true);
+        insert_key(&conn, "b", "sk-b", true);
+        let settings_file = temp_settings("sk-b");
+
+        let tx = conn.transaction().unwrap();
+        replace_key(tx, "a", &settings_file, crypto::encrypt_secret("sk-a2").unwrap(), "rotate", None).unwrap();
+        assert_eq!(settings_api_key(&settings_file).unwrap().as_deref(), Some("sk-b"));
+
+        let tx = conn.transaction().unwrap();
+        replace_key(tx, "b", &settings_file, crypto::encrypt_secret("sk-b2").unwrap(), "rotate", None).unwrap();
+        assert_eq!(settings_api_key(&settings_file).unwrap().as_deref(), Some("sk-b2"));
+        fs::remove_dir_all(settings_file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn listed_keys_are_masked_and_masked_edits_are_not_new_keys() {
+        crypto::init_test_master_key();
+        let stored = crypto::encrypt_secret("sk-ant-api03-secret-value").unwrap();
+        let listed = masked(ApiKey {
+            id: "a".to_string(),
+            name: "a".to_string(),
+            anthropic_api_key: stored,
+            description: None,
+            anthropic_base_url: None,
+            is_active: true,
+            expires_at: None,
+            tags: Vec::new(),
+            owner: None,
+            last_used_at: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }).unwrap();
+
+        assert_eq!(listed.anthropic_api_key, crypto::mask_secret("sk-ant-api03-secret-value"));
+        assert!(!is_new_key(&listed.anthropic_api_key, "sk-ant-api03-secret-value"));
+        assert!(!is_new_key("sk-ant-api03-secret-value", "sk-ant-api03-secret-value"));
+        assert!(is_new_key("sk-ant-api03-other-value", "sk-ant-api03-secret-value"));
+    }
+
+    #[test]
+    fn keys_used_by_saved_router_profiles_are_referenced() {
+        let conn = db::open_test_connection();
+        let config = r#"{
+            "Providers": [
+                { "name": "openrouter", "api_base_url": "https://openrouter.ai/api/v1/chat/completions", "api_key_id": "key-1", "models": [] },
+                { "name": "deepseek", "api_base_url": "https://api.deepseek.com/chat/completions", "api_key": "$DEEPSEEK_KEY", "models": [] }
+            ]
+        }"#;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO router_profiles (id, name, description, config, is_active, created_at, updated_at) VALUES ('p1', 'work', NULL, ?1, 0, ?2, ?2)",
+            (config, &now),
+        ).unwrap();
+
+        assert_eq!(key_references(&conn, "key-1").unwrap(), vec!["work/openrouter".to_string()]);
+        assert!(key_references(&conn, "key-2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleting_refuses_referenced_keys_and_removes_history() {
+        let mut conn = db::open_test_connection();
+        insert_key(&conn, "a", "sk-a", true);
+        insert_key(&conn, "b", "sk-b", false);
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO router_profiles (id, name, description, config, is_active, created_at, updated_at) VALUES ('p1', 'work', NULL, ?1, 0, ?2, ?2)",
+            (r#"{ "Providers": [{ "name": "openrouter", "api_base_url": "https://openrouter.ai/api/v1/chat/completions", "api_key_id": "a", "models": [] }] }"#, &now),
+        ).unwrap();
+        let tx = conn.transaction().unwrap();
+        replace_key(tx, "b", Path::new("/nonexistent/settings.json"), crypto::encrypt_secret("sk-b2").unwrap(), "rotate", None).unwrap();
+
+        let count = |conn: &rusqlite::Connection, sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM api_key_history WHERE api_key_id = 'b'"), 1);
+
+        let error = delete_key(&mut conn, "a").unwrap_err();
+        assert!(error.contains("work/openrouter"), "{}", error);
+        assert!(delete_key(&mut conn, "b").unwrap());
+        assert!(!delete_key(&mut conn, "b").unwrap());
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM api_keys WHERE id = 'a'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM api_keys WHERE id = 'b'"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM api_key_history WHERE api_key_id = 'b'"), 0);
+    }
+
+    fn stored(id: &str, api_key: &str, base_url: Option<&str>, is_active: bool) -> StoredKey {
+        StoredKey {
+            id: id.to_string(),
+            name: format!("{} key", id),
+            api_key: api_key.to_string(),
+            base_url: base_url.map(|u| u.to_string()),
+            is_active,
+        }
+    }
+
+    fn drift_keys() -> Vec<StoredKey> {
+        vec![
+            stored("a", "sk-a", Some("https://api.example.com/"), true),
+            stored("b", "sk-b", None, false),
+        ]
+    }
+
+    #[test]
+    fn drift_reports_in_sync_when_the_active_key_and_url_match() {
+        let env = serde_json::json!({ "ANTHROPIC_API_KEY": "sk-a", "ANTHROPIC_BASE_URL": "https://api.example.com" });
+        let report = compare_with_settings("settings.json", &env, &drift_keys());
+
+        assert_eq!(report.status, "in_sync");
+        assert_eq!(report.matched_key_id.as_deref(), Some("a"));
+        assert!(report.base_url_matches);
+        assert_eq!(report.disk_api_key, Some(crypto::mask_secret("sk-a")));
+        assert_eq!(report.active_key_ids, ["a"]);
+    }
+
+    #[test]
+    fn drift_reports_a_mismatched_key_or_base_url() {
+        // 文件里是已弃用的密钥
+        let inactive = compare_with_settings("settings.json", &serde_json::json!({ "ANTHROPIC_AUTH_TOKEN": "sk-b" }), &drift_keys());
+        assert_eq!(inactive.status, "drift");
+        assert_eq!(inactive.matched_key_id.as_deref(), Some("b"));
+
+        let other_url = serde_json::json!({ "ANTHROPIC_API_KEY": "sk-a", "ANTHROPIC_BASE_URL": "https://proxy.example.com" });
+        let report = compare_with_settings("settings.json", &other_url, &drift_keys());
+        assert_eq!(report.status, "drift");
+        assert!(!report.base_url_matches);
+    }
+
+    #[test]
+    fn drift_reports_no_key_for_a_missing_settings_file() {
+        let missing = std::env::temp_dir().join(format!("claude-meta-missing-{}", uuid::Uuid::new_v4())).join("settings.json");
+        let env = settings_env(&missing).unwrap();
+        let report = compare_with_settings(&missing.to_string_lossy(), &env, &drift_keys());
+
+        assert_eq!(report.status, "no_key");
+        assert_eq!(report.disk_api_key, None);
+        assert_eq!(report.matched_key_id, None);
+    }
+
+    #[test]
+    fn drift_reports_keys_that_are_not_stored() {
+        let report = compare_with_settings("settings.json", &serde_json::json!({ "ANTHROPIC_API_KEY": "sk-unknown" }), &drift_keys());
+
+        assert_eq!(report.status, "unknown_key");
+        assert_eq!(report.matched_key_id, None);
+        assert!(!report.base_url_matches);
+    }
+
+    #[test]
+    fn expiry_accepts_rfc3339_timestamps_and_plain_dates() {
+        let timestamp = parse_expiry("2026-03-01T08:30:00+08:00").unwrap();
+        assert_eq!(timestamp.to_rfc3339(), "2026-03-01T00:30:00+00:00");
+
+        // 只有日期时按当天结束（UTC）计算
+        let date = parse_expiry("2026-03-01").unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-03-01T23:59:59+00:00");
+    }
+
+    #[test]
+    fn invalid_expiry_values_are_rejected() {
+        for value in ["", "2026-02-30", "03/01/2026", "2026-03-01 08:30", "tomorrow"] {
+            assert!(parse_expiry(value).is_none(), "{}", value);
+            assert!(validate_expiry(&Some(value.to_string())).is_err(), "{}", value);
+        }
+        assert!(validate_expiry(&None).is_ok());
+    }
+
+    #[test]
+    fn expiring_keys_include_the_window_boundary_and_expired_keys() {
+        let conn = db::open_test_connection();
+        let now = parse_expiry("2026-03-01T12:00:00Z").unwrap();
+        for (id, expires_at) in [
+            ("inside", Some("2026-03-05T00:00:00Z")),
+            ("boundary", Some("2026-03-08T12:00:00Z")),
+            ("outside", Some("2026-03-08T12:00:01Z")),
+            ("expired", Some("2026-02-01")),
+            ("never", None),
+        ] {
+            insert_key(&conn, id, "sk-x", true);
+            conn.execute("UPDATE api_keys SET expires_at = ?1 WHERE id = ?2", (expires_at, id)).unwrap();
+        }
+
+        let ids = |days| expiring_keys(&conn, days, now).unwrap().into_iter().map(|k| k.id).collect::<Vec<_>>();
+        assert_eq!(ids(7), ["expired", "inside", "boundary"]);
+        // 负数按 0 天处理，只剩已过期的
+        assert_eq!(ids(-3), ["expired"]);
+    }
 }
@@ -44,18 +44,28 @@ pub async fn backup_config_file(app: tauri::AppHandle, backup_filename: String)
     // Get database connection
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
     
-    // Get current timestamp
+    insert_backup(&conn, &backup_filename, &content)?;
+    
+    println!("Backup completed successfully");
+    Ok(true)
+}
+
+/// Saves a snapshot of the settings file into the backups table.
+pub fn insert_backup(conn: &rusqlite::Connection, backup_filename: &str, content: &str) -> Result<(), String> {
     let now = chrono::Utc::now().to_rfc3339();
     let size = content.len() as i64;
     
-    // Save backup to database
     conn.execute(
         "INSERT INTO backups (filename, content, size, created_at) VALUES (?1, ?2, ?3, ?4)",
-        (&backup_filename, &content, &size, &now),
+        (backup_filename, content, &size, &now),
     ).map_err(|e| format!("Failed to save backup to database: {}", e))?;
     
-    println!("Backup completed successfully");
-    Ok(true)
+    Ok(())
+}
+
+/// Backup name used for automatic backups, matching the format the frontend uses.
+pub fn auto_backup_filename() -> String {
+    format!("claude_config_backup_{}.json", chrono::Utc::now().format("%Y-%m-%dT%H-%M-%S"))
 }
 
 #[tauri::command]
@@ -1,10 +1,13 @@
 use tauri::AppHandle;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use crate::db;
+use crate::crypto;
+use crate::commands::settings_schema;
 use crate::models::{ClaudeSettings, EnvConfig, PermissionsConfig, ConfigFileFormat};
 use serde_json;
 use dirs;
+use rusqlite::OptionalExtension;
 
 #[tauri::command]
 pub async fn get_config_file_content(app: AppHandle) -> Result<String, String> {
@@ -95,7 +98,8 @@ pub async fn save_config_file_content(app: AppHandle, content: String) -> Result
     }
     
     // 验证JSON格式
-    serde_json::from_str::<serde_json::Value>(&content).map_err(|e| format!("无效的JSON格式: {}", e))?;
+    let settings = serde_json::from_str::<serde_json::Value>(&content).map_err(|e| format!("无效的JSON格式: {}", e))?;
+    settings_schema::check_settings(&settings)?;
     
     fs::write(&settings_file, content).map_err(|e| format!("Failed to write file: {}", e))?;
     
@@ -121,18 +125,16 @@ pub async fn get_claude_settings(path: String) -> Result<String, String> {
         }
         
         // Create default settings
+        let mut env = serde_json::Map::new();
+        env.insert("ANTHROPIC_BASE_URL".to_string(), serde_json::Value::String("https://api.anthropic.com".to_string()));
         let default_settings = ClaudeSettings {
-            anthropic_api_key: None,
-            anthropic_base_url: Some("https://api.anthropic.com".to_string()),
-            model: Some("claude-3-5-sonnet-20241022".to_string()),
-            max_tokens: Some(4096),
-            temperature: Some(0.7),
-            top_p: Some(0.9),
-            timeout: Some(30000),
-            proxy_url: None,
-            verbose: Some(false),
-            stream: Some(true),
-            unsafe_html: Some(false),
+            env: Some(env),
+            permissions: Some(PermissionsConfig {
+                allow: Some(Vec::new()),
+                deny: Some(Vec::new()),
+                ..Default::default()
+            }),
+            ..Default::default()
         };
         
         let content = serde_json::to_string_pretty(&default_settings)
@@ -145,8 +147,10 @@ pub async fn get_claude_settings(path: String) -> Result<String, String> {
     fs::read_to_string(&settings_file).map_err(|e| format!("Failed to read file: {}", e))
 }
 
+/// Validates `settings` against the schema, so errors point at the offending key, then writes
+/// it as given. Keys the app does not model are kept.
 #[tauri::command]
-pub async fn save_claude_settings(path: String, settings: ClaudeSettings) -> Result<bool, String> {
+pub async fn save_claude_settings(path: String, settings: serde_json::Value) -> Result<bool, String> {
     // Expand the ~ to home directory
     let expanded_path = if path.starts_with("~/") {
         let home_dir = dirs::home_dir().ok_or("Failed to get home directory")?;
@@ -162,6 +166,11 @@ pub async fn save_claude_settings(path: String, settings: ClaudeSettings) -> Res
         fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
     }
     
+    settings_schema::check_settings(&settings)?;
+    // 模式校验之外再确认能解析成 ClaudeSettings，写入的仍是原始内容以保留键的顺序
+    serde_json::from_value::<ClaudeSettings>(settings.clone())
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    
     let content = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
     
@@ -170,40 +179,61 @@ pub async fn save_claude_settings(path: String, settings: ClaudeSettings) -> Res
     Ok(true)
 }
 
-#[tauri::command]
-pub async fn update_config_env(config_path: String, api_key: String, base_url: Option<String>) -> Result<bool, String> {
-    // Expand the ~ to home directory if needed
-    let expanded_path = if config_path.starts_with("~/") {
+/// Expands a leading `~/` to the user's home directory.
+pub fn expand_home_path(path: &str) -> Result<PathBuf, String> {
+    if let Some(relative) = path.strip_prefix("~/") {
         let home_dir = dirs::home_dir().ok_or("Failed to get home directory")?;
-        home_dir.join(&config_path[2..])
+        Ok(home_dir.join(relative))
     } else {
-        std::path::PathBuf::from(config_path)
+        Ok(PathBuf::from(path))
+    }
+}
+
+/// Writes the stored key `api_key_id` into the settings file at `config_path`, or removes the
+/// key from it when no id is given. Secrets are only decrypted here, never by the caller.
+#[tauri::command]
+pub async fn update_config_env(app: AppHandle, config_path: String, api_key_id: Option<String>) -> Result<bool, String> {
+    let settings_file = expand_home_path(&config_path)?;
+    
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    let Some(id) = api_key_id else {
+        write_env_to_settings(&settings_file, "", None)?;
+        return Ok(true);
     };
     
-    let settings_file = expanded_path;
+    let (stored_key, base_url): (String, Option<String>) = conn.query_row(
+        "SELECT ANTHROPIC_API_KEY, ANTHROPIC_BASE_URL FROM api_keys WHERE id = ?1",
+        [&id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or("API key not found")?;
+    let api_key = crypto::decrypt_secret(&stored_key)?;
     
-    println!("Updating config env with API key: {}", api_key);
     println!("Base URL: {:?}", base_url);
     println!("Settings file path: {:?}", settings_file);
     
-    // Template configuration structure
-    let template_config = serde_json::json!({
-        "env": {
-            "ANTHROPIC_API_KEY": "",
-            "ANTHROPIC_AUTH_TOKEN": "",
-            "ANTHROPIC_BASE_URL": "https://api.packycode.com",
-            "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC": 1
-        },
-        "permissions": {
-            "allow": [],
-            "deny": []
-        },
-        "apiKeyHelper": "echo 'your-api-key-here'"
-    });
+    write_env_to_settings(&settings_file, &api_key, base_url)?;
     
-    // Read existing config or use template
+    conn.execute(
+        "UPDATE api_keys SET last_used_at = ?1 WHERE id = ?2",
+        (chrono::Utc::now().to_rfc3339(), &id),
+    ).map_err(|e| e.to_string())?;
+    
+    println!("Config env updated successfully");
+    Ok(true)
+}
+
+/// Writes the API key and base URL into the `env` block of a Claude settings file,
+/// preserving every other key in the file.
+///
+/// A key without a base URL removes any `ANTHROPIC_BASE_URL` left by a previous key, and an
+/// empty `api_key` removes both. `permissions` are never touched, and an `apiKeyHelper` is only
+/// removed when it is the `echo '<key>'` form older versions wrote, so the plaintext key does
+/// not stay in the file.
+pub fn write_env_to_settings(settings_file: &Path, api_key: &str, base_url: Option<String>) -> Result<(), String> {
+    // Read existing config or start from an empty object
     let mut config_obj = if settings_file.exists() {
-        let content = fs::read_to_string(&settings_file)
+        let content = fs::read_to_string(settings_file)
             .map_err(|e| format!("Failed to read settings file: {}", e))?;
         
         // Parse existing config to preserve structure
@@ -214,86 +244,137 @@ pub async fn update_config_env(config_path: String, api_key: String, base_url: O
         if let Some(parent) = settings_file.parent() {
             fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
         }
-        
-        // Use template as base
-        template_config.clone()
+        serde_json::json!({})
     };
+    let config_map = config_obj.as_object_mut()
+        .ok_or("Settings file must contain a JSON object")?;
     
-    // Update only specific fields while preserving the structure
-    if let Some(env_obj) = config_obj.get_mut("env") {
-        if let Some(env_map) = env_obj.as_object_mut() {
-            // Update both ANTHROPIC_API_KEY and ANTHROPIC_AUTH_TOKEN fields
-            if api_key.is_empty() {
-                env_map.remove("ANTHROPIC_API_KEY");
-                env_map.remove("ANTHROPIC_AUTH_TOKEN");
-            } else {
-                env_map.insert("ANTHROPIC_API_KEY".to_string(), serde_json::Value::String(api_key.clone()));
-                env_map.insert("ANTHROPIC_AUTH_TOKEN".to_string(), serde_json::Value::String(api_key.clone()));
-            }
-            
-            // Update ANTHROPIC_BASE_URL if provided
-            if let Some(url) = base_url {
-                if !url.is_empty() {
-                    env_map.insert("ANTHROPIC_BASE_URL".to_string(), serde_json::Value::String(url));
-                }
-            }
-            
-            // Always ensure CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC is set to 1
-            env_map.insert("CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC".to_string(), serde_json::Value::Number(serde_json::Number::from(1)));
-        }
-    } else {
-        // If env doesn't exist, create it
-        let mut env_map = serde_json::Map::new();
-        
-        if !api_key.is_empty() {
-            env_map.insert("ANTHROPIC_API_KEY".to_string(), serde_json::Value::String(api_key.clone()));
-            env_map.insert("ANTHROPIC_AUTH_TOKEN".to_string(), serde_json::Value::String(api_key.clone()));
+    if !config_map.get("env").is_some_and(|v| v.is_object()) {
+        config_map.insert("env".to_string(), serde_json::json!({}));
+    }
+    if let Some(env_map) = config_map.get_mut("env").and_then(|v| v.as_object_mut()) {
+        if api_key.is_empty() {
+            env_map.shift_remove("ANTHROPIC_API_KEY");
+            env_map.shift_remove("ANTHROPIC_AUTH_TOKEN");
+        } else {
+            env_map.insert("ANTHROPIC_API_KEY".to_string(), serde_json::Value::String(api_key.to_string()));
+            env_map.insert("ANTHROPIC_AUTH_TOKEN".to_string(), serde_json::Value::String(api_key.to_string()));
         }
         
-        if let Some(url) = base_url.as_ref() {
-            if !url.is_empty() {
-                env_map.insert("ANTHROPIC_BASE_URL".to_string(), serde_json::Value::String(url.clone()));
+        // 没有 base URL 的密钥走默认地址，删除上一个密钥留下的地址
+        match base_url.filter(|url| !url.is_empty()) {
+            Some(url) if !api_key.is_empty() => {
+                env_map.insert("ANTHROPIC_BASE_URL".to_string(), serde_json::Value::String(url));
+            }
+            _ => {
+                env_map.shift_remove("ANTHROPIC_BASE_URL");
             }
         }
         
+        // Always ensure CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC is set to 1
         env_map.insert("CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC".to_string(), serde_json::Value::Number(serde_json::Number::from(1)));
-        
-        config_obj.as_object_mut().unwrap().insert("env".to_string(), serde_json::Value::Object(env_map));
     }
     
-    // Update apiKeyHelper to match the API key and clean up old field
-    config_obj.as_object_mut().unwrap().remove("api_key_helper"); // Remove old field name
-    if api_key.is_empty() {
-        config_obj.as_object_mut().unwrap().remove("apiKeyHelper");
-    } else {
-        config_obj.as_object_mut().unwrap().insert("apiKeyHelper".to_string(), serde_json::Value::String(format!("echo '{}'", api_key)));
-    }
-    
-    // Ensure permissions structure exists with correct array types
-    if let Some(perm_obj) = config_obj.get_mut("permissions") {
-        if let Some(perm_map) = perm_obj.as_object_mut() {
-            // Ensure allow is an array
-            if !perm_map.get("allow").map_or(false, |v| v.is_array()) {
-                perm_map.insert("allow".to_string(), serde_json::json!([]));
-            }
-            // Ensure deny is an array
-            if !perm_map.get("deny").map_or(false, |v| v.is_array()) {
-                perm_map.insert("deny".to_string(), serde_json::json!([]));
-            }
-        }
-    } else {
-        config_obj.as_object_mut().unwrap().insert("permissions".to_string(), serde_json::json!({
-            "allow": [],
-            "deny": []
-        }));
+    // 旧版本写入的 apiKeyHelper 是明文密钥的 echo 命令，需要清理；用户自己配置的脚本保持不变
+    config_map.shift_remove("api_key_helper"); // Remove old field name
+    if config_map.get("apiKeyHelper").and_then(|v| v.as_str()).is_some_and(is_echo_key_helper) {
+        config_map.shift_remove("apiKeyHelper");
     }
     
     let content = serde_json::to_string_pretty(&config_obj)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
     
-    fs::write(&settings_file, content)
+    fs::write(settings_file, content)
         .map_err(|e| format!("Failed to write settings file: {}", e))?;
     
-    println!("Config env updated successfully");
-    Ok(true)
+    Ok(())
+}
+
+fn is_echo_key_helper(helper: &str) -> bool {
+    let helper = helper.trim();
+    helper.starts_with("echo '") && helper.ends_with('\'') && !helper.contains(['|', ';', '&', '$'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_settings(initial: Option<serde_json::Value>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("claude-meta-config-{}", uuid::Uuid::new_v4()));
+        let file = dir.join("settings.json");
+        if let Some(initial) = initial {
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(&file, initial.to_string()).unwrap();
+        }
+        file
+    }
+
+    fn read(file: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(file).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn new_file_only_gets_the_key() {
+        let file = temp_settings(None);
+        write_env_to_settings(&file, "sk-ant-secret", None).unwrap();
+
+        let settings = read(&file);
+        assert_eq!(settings["env"]["ANTHROPIC_API_KEY"], "sk-ant-secret");
+        assert!(settings["env"].get("ANTHROPIC_BASE_URL").is_none());
+        assert!(settings.get("apiKeyHelper").is_none());
+        assert!(settings.get("permissions").is_none());
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn replaces_the_previous_base_url_and_keeps_permissions() {
+        let permissions = serde_json::json!({ "allow": ["Bash(npm test)"], "defaultMode": "plan" });
+        let file = temp_settings(Some(serde_json::json!({
+            "env": { "ANTHROPIC_BASE_URL": "https://old.example.com", "FOO": "bar" },
+            "permissions": permissions,
+        })));
+
+        write_env_to_settings(&file, "sk-ant-secret", Some("https://new.example.com".to_string())).unwrap();
+        let settings = read(&file);
+        assert_eq!(settings["env"]["ANTHROPIC_BASE_URL"], "https://new.example.com");
+        assert_eq!(settings["env"]["FOO"], "bar");
+        assert_eq!(settings["permissions"], permissions);
+
+        write_env_to_settings(&file, "sk-ant-other", None).unwrap();
+        let settings = read(&file);
+        assert!(settings["env"].get("ANTHROPIC_BASE_URL").is_none());
+        assert_eq!(settings["permissions"], permissions);
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn removing_keys_keeps_the_order_of_the_rest() {
+        let file = temp_settings(None);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, r#"{"model":"opus","env":{"ANTHROPIC_BASE_URL":"https://old.example.com","A":"1","B":"2","C":"3"},"apiKeyHelper":"echo 'sk-ant-old'","hooks":{},"statusLine":{}}"#).unwrap();
+
+        write_env_to_settings(&file, "sk-ant-secret", None).unwrap();
+        let settings = read(&file);
+        let keys: Vec<&String> = settings.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["model", "env", "hooks", "statusLine"]);
+        let env_keys: Vec<&String> = settings["env"].as_object().unwrap().keys().collect();
+        assert_eq!(env_keys[..3], ["A", "B", "C"]);
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn removes_legacy_echo_helpers_but_keeps_custom_ones() {
+        let file = temp_settings(Some(serde_json::json!({ "apiKeyHelper": "echo 'sk-ant-old'" })));
+        write_env_to_settings(&file, "sk-ant-secret", None).unwrap();
+        assert!(read(&file).get("apiKeyHelper").is_none());
+        assert!(!fs::read_to_string(&file).unwrap().contains("sk-ant-old"));
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+
+        let file = temp_settings(Some(serde_json::json!({ "apiKeyHelper": "~/bin/get-key.sh" })));
+        write_env_to_settings(&file, "", None).unwrap();
+        let settings = read(&file);
+        assert_eq!(settings["apiKeyHelper"], "~/bin/get-key.sh");
+        assert!(settings["env"].get("ANTHROPIC_API_KEY").is_none());
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
 }
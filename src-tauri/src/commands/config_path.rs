@@ -113,21 +113,28 @@ pub async fn delete_config_path(app: tauri::AppHandle, id: String) -> Result<boo
 
 #[tauri::command]
 pub async fn save_config_path(app: tauri::AppHandle, path: String) -> Result<bool, String> {
-    let conn = get_database_connection(&app).map_err(|e| e.to_string())?;
-    
-    let now = chrono::Utc::now().to_rfc3339();
-    
-    println!("Saving config path: {}", path);
-    
-    // Delete any existing config path
-    conn.execute("DELETE FROM current_config_path", ())
-        .map_err(|e| e.to_string())?;
+    {
+        let conn = get_database_connection(&app).map_err(|e| e.to_string())?;
+        
+        let now = chrono::Utc::now().to_rfc3339();
+        
+        println!("Saving config path: {}", path);
+        
+        // Delete any existing config path
+        conn.execute("DELETE FROM current_config_path", ())
+            .map_err(|e| e.to_string())?;
+        
+        // Insert the new config path
+        conn.execute(
+            "INSERT INTO current_config_path (path, updated_at) VALUES (?1, ?2)",
+            (&path, &now),
+        ).map_err(|e| e.to_string())?;
+    }
     
-    // Insert the new config path
-    conn.execute(
-        "INSERT INTO current_config_path (path, updated_at) VALUES (?1, ?2)",
-        (&path, &now),
-    ).map_err(|e| e.to_string())?;
+    // 配置文件换了位置，监听也要跟着切换
+    if let Err(e) = super::watcher::restart_config_watcher(&app).await {
+        eprintln!("Failed to restart config watcher: {}", e);
+    }
     
     println!("Config path saved successfully");
     Ok(true)
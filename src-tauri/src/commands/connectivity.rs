@@ -0,0 +1,235 @@
+// src-tauri/src/commands/connectivity.rs
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use rusqlite::OptionalExtension;
+use crate::crypto;
+use crate::db;
+
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConnectivityResult {
+    pub url: String,
+    /// One of `ok`, `auth_error`, `http_error`, `timeout` or `network_error`. Provider health
+    /// checks also use `config_error` when a provider's key cannot be resolved.
+    pub status: String,
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    pub message: Option<String>,
+    /// Response body of a successful probe, for callers that need to inspect it.
+    #[serde(skip)]
+    pub body: Option<String>,
+}
+
+/// Sends a GET request to `url` and classifies the outcome, separating credential
+/// problems (401/403) from other HTTP errors and from network failures.
+pub async fn probe_endpoint(url: &str, headers: &[(&str, String)]) -> ConnectivityResult {
+    probe_endpoint_with_timeout(url, headers, PROBE_TIMEOUT).await
+}
+
+async fn probe_endpoint_with_timeout(url: &str, headers: &[(&str, String)], timeout: Duration) -> ConnectivityResult {
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => return network_failure(url, 0, &e),
+    };
+
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+
+    let started = Instant::now();
+    let response = request.send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return network_failure(url, latency_ms, &e),
+    };
+
+    let http_status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let status = if http_status.is_success() {
+        "ok"
+    } else if http_status == reqwest::StatusCode::UNAUTHORIZED || http_status == reqwest::StatusCode::FORBIDDEN {
+        "auth_error"
+    } else {
+        "http_error"
+    };
+
+    ConnectivityResult {
+        url: url.to_string(),
+        status: status.to_string(),
+        http_status: Some(http_status.as_u16()),
+        latency_ms,
+        message: if http_status.is_success() { None } else { Some(error_message(&body, http_status)) },
+        body: if http_status.is_success() { Some(body) } else { None },
+    }
+}
+
+fn network_failure(url: &str, latency_ms: u64, error: &reqwest::Error) -> ConnectivityResult {
+    ConnectivityResult {
+        url: url.to_string(),
+        status: if error.is_timeout() { "timeout" } else { "network_error" }.to_string(),
+        http_status: None,
+        latency_ms,
+        message: Some(error.to_string()),
+        body: None,
+    }
+}
+
+// Anthropic and OpenAI-compatible APIs both nest the reason under `error.message`
+fn error_message(body: &str, status: reqwest::StatusCode) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().or_else(|| v["message"].as_str()).map(|s| s.to_string()))
+        .unwrap_or_else(|| {
+            let snippet: String = body.chars().take(200).collect();
+            if snippet.is_empty() { status.to_string() } else { snippet }
+        })
+}
+
+/// Checks that an Anthropic API key is accepted at `base_url` by listing the available models.
+pub async fn probe_anthropic_key(api_key: &str, base_url: Option<&str>) -> ConnectivityResult {
+    let base_url = base_url
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or(DEFAULT_ANTHROPIC_BASE_URL)
+        .trim_end_matches('/');
+    let url = format!("{}/v1/models", base_url);
+
+    let mut headers = vec![
+        ("x-api-key", api_key.to_string()),
+        ("anthropic-version", ANTHROPIC_VERSION.to_string()),
+    ];
+    // 第三方中转通常只认 Authorization（对应 ANTHROPIC_AUTH_TOKEN），官方接口只认 x-api-key
+    if base_url != DEFAULT_ANTHROPIC_BASE_URL {
+        headers.push(("authorization", format!("Bearer {}", api_key)));
+    }
+    probe_endpoint(&url, &headers).await
+}
+
+#[tauri::command]
+pub async fn test_api_key(app: AppHandle, id: String) -> Result<ConnectivityResult, String> {
+    let (stored_key, base_url): (String, Option<String>) = {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT ANTHROPIC_API_KEY, ANTHROPIC_BASE_URL FROM api_keys WHERE id = ?1",
+            [&id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().map_err(|e| e.to_string())?
+        .ok_or("API key not found")?
+    };
+
+    let api_key = crypto::decrypt_secret(&stored_key)?;
+    Ok(probe_anthropic_key(&api_key, base_url.as_deref()).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    /// Serves a single request with `status` and `body` after `delay`, and hands back the raw request.
+    async fn serve_once(status: &'static str, body: &'static str, delay: Duration) -> (String, oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let _ = tx.send(String::from_utf8_lossy(&request).to_string());
+
+            tokio::time::sleep(delay).await;
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status, body.len(), body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn reports_ok_with_body_and_sends_key_headers() {
+        let (url, request) = serve_once("200 OK", r#"{"data":[]}"#, Duration::ZERO).await;
+        let result = probe_anthropic_key("sk-test", Some(&url)).await;
+
+        assert_eq!(result.status, "ok");
+        assert_eq!(result.http_status, Some(200));
+        assert_eq!(result.body.as_deref(), Some(r#"{"data":[]}"#));
+        assert!(result.message.is_none());
+
+        let request = request.await.unwrap().to_lowercase();
+        assert!(request.starts_with("get /v1/models "), "{}", request);
+        assert!(request.contains("x-api-key: sk-test"));
+        assert!(request.contains("authorization: bearer sk-test"));
+    }
+
+    #[tokio::test]
+    async fn classifies_401_and_403_as_auth_errors() {
+        for status in ["401 Unauthorized", "403 Forbidden"] {
+            let (url, _) = serve_once(status, r#"{"error":{"message":"invalid x-api-key"}}"#, Duration::ZERO).await;
+            let result = probe_endpoint(&url, &[]).await;
+
+            assert_eq!(result.status, "auth_error", "{}", status);
+            assert_eq!(result.message.as_deref(), Some("invalid x-api-key"));
+            assert!(result.body.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn classifies_other_statuses_as_http_errors() {
+        let (url, _) = serve_once("500 Internal Server Error", "upstream exploded", Duration::ZERO).await;
+        let result = probe_endpoint(&url, &[]).await;
+
+        assert_eq!(result.status, "http_error");
+        assert_eq!(result.http_status, Some(500));
+        assert_eq!(result.message.as_deref(), Some("upstream exploded"));
+    }
+
+    #[tokio::test]
+    async fn reports_refused_connections_as_network_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let result = probe_endpoint(&url, &[]).await;
+        assert_eq!(result.status, "network_error");
+        assert!(result.http_status.is_none());
+        assert!(result.message.is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_slow_servers_as_timeouts() {
+        let (url, _) = serve_once("200 OK", "{}", Duration::from_secs(5)).await;
+        let result = probe_endpoint_with_timeout(&url, &[], Duration::from_millis(200)).await;
+
+        assert_eq!(result.status, "timeout");
+        assert!(result.http_status.is_none());
+    }
+
+    #[tokio::test]
+    async fn measures_latency_until_the_response_arrives() {
+        let (url, _) = serve_once("200 OK", "{}", Duration::from_millis(150)).await;
+        let result = probe_endpoint(&url, &[]).await;
+
+        assert_eq!(result.status, "ok");
+        assert!(result.latency_ms >= 150, "latency {}ms", result.latency_ms);
+    }
+}
@@ -9,3 +9,17 @@ pub mod router;
 pub mod utils;
 pub mod ide;
 pub mod category;
+pub mod watcher;
+pub mod connectivity;
+pub mod provider_health;
+pub mod router_validation;
+pub mod router_profiles;
+pub mod router_process;
+pub mod router_logs;
+pub mod transformers;
+pub mod provider_presets;
+pub mod permissions;
+pub mod permission_simulator;
+pub mod settings_layers;
+pub mod project_settings;
+pub mod settings_schema;
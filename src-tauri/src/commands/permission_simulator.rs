@@ -0,0 +1,496 @@
+// src-tauri/src/commands/permission_simulator.rs
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::permissions::{parse_rule, permissions_of, read_settings, resolve_settings_file};
+use crate::models::PermissionsConfig;
+
+// Read 规则同样约束只读的搜索工具，Edit 规则约束所有会修改文件的工具
+const READ_TOOLS: [&str; 5] = ["Read", "Glob", "Grep", "LS", "NotebookRead"];
+const EDIT_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+// 不需要确认即可运行的工具
+const READ_ONLY_TOOLS: [&str; 6] = ["Read", "Glob", "Grep", "LS", "NotebookRead", "TodoWrite"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionCheckResult {
+    /// `allow`, `deny` or `ask`.
+    pub decision: String,
+    /// The rule that decided the outcome, if any; otherwise the default mode did.
+    pub matched_rule: Option<String>,
+    /// The list `matched_rule` came from.
+    pub matched_list: Option<String>,
+    pub default_mode: String,
+    pub reason: String,
+    /// Rules that could not be parsed and were ignored, as Claude Code does.
+    pub skipped_rules: Vec<String>,
+    pub settings_file: String,
+}
+
+struct MatchContext {
+    cwd: PathBuf,
+    project_root: PathBuf,
+    home: Option<PathBuf>,
+}
+
+/// Resolves `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => { parts.pop(); }
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            _ => {}
+        }
+    }
+    parts
+}
+
+/// Matches `*` and `?` within one path segment, backtracking only to the most recent `*`.
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置，以及它当前吞掉的文本结束位置
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, t));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn segments_match(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => (0..=path.len()).any(|i| segments_match(rest, &path[i..])),
+        Some((first, rest)) => path.first().is_some_and(|segment| {
+            let pattern: Vec<char> = first.chars().collect();
+            let segment: Vec<char> = segment.chars().collect();
+            wildcard_match(&pattern, &segment) && segments_match(rest, &path[1..])
+        }),
+    }
+}
+
+/// Matches a gitignore-style path rule. `//` is absolute, `~/` is the home directory,
+/// `/` is the project root and anything else is relative to `cwd`.
+fn path_matches(specifier: &str, path: &str, ctx: &MatchContext) -> bool {
+    let pattern = if let Some(absolute) = specifier.strip_prefix("//") {
+        PathBuf::from("/").join(absolute)
+    } else if let Some(relative) = specifier.strip_prefix("~/") {
+        match &ctx.home {
+            Some(home) => home.join(relative),
+            None => return false,
+        }
+    } else if let Some(relative) = specifier.strip_prefix('/') {
+        ctx.project_root.join(relative)
+    } else if specifier.trim_end_matches('/').contains('/') {
+        ctx.cwd.join(specifier)
+    } else {
+        // 不含斜杠的模式（如 `.env`）与 gitignore 一样匹配任意层级
+        ctx.cwd.join("**").join(specifier)
+    };
+
+    let path = match path.strip_prefix("~/") {
+        Some(relative) => match &ctx.home {
+            Some(home) => home.join(relative),
+            None => return false,
+        },
+        None => ctx.cwd.join(path),
+    };
+
+    let pattern = normalize(&pattern);
+    let path = normalize(&path);
+    // 目录命中时其下的所有文件也算命中
+    (0..=path.len()).any(|len| segments_match(&pattern, &path[..len]))
+}
+
+fn bash_matches(specifier: &str, command: &str) -> bool {
+    match specifier.strip_suffix(":*").map(str::trim_end) {
+        // 前缀按整词匹配：Bash(npm run:*) 不应放行 npm runner
+        Some("") => true,
+        Some(prefix) => command == prefix || command.strip_prefix(prefix).is_some_and(|rest| rest.starts_with(' ')),
+        None => command == specifier,
+    }
+}
+
+fn domain_matches(specifier: &str, input: &str) -> bool {
+    let Some(domain) = specifier.strip_prefix("domain:") else { return false };
+    let url = reqwest::Url::parse(input).or_else(|_| reqwest::Url::parse(&format!("https://{}", input)));
+    let Some(host) = url.ok().and_then(|u| u.host_str().map(|h| h.to_string())) else { return false };
+    match domain.strip_prefix("*.") {
+        Some(parent) => host.ends_with(&format!(".{}", parent)),
+        None => host == domain,
+    }
+}
+
+fn rule_matches(rule: &str, tool: &str, input: Option<&str>, ctx: &MatchContext) -> Result<bool, String> {
+    let (rule_tool, specifier) = parse_rule(rule)?;
+
+    if let Some(server) = rule_tool.strip_prefix("mcp__") {
+        return Ok(tool == rule_tool || (!server.contains("__") && tool.starts_with(&format!("{}__", rule_tool))));
+    }
+
+    let applies = rule_tool == tool
+        || (rule_tool == "Read" && READ_TOOLS.contains(&tool))
+        || (rule_tool == "Edit" && EDIT_TOOLS.contains(&tool));
+    if !applies {
+        return Ok(false);
+    }
+
+    let Some(specifier) = specifier else { return Ok(true) };
+    let Some(input) = input else { return Ok(false) };
+    Ok(match rule_tool {
+        "Bash" => bash_matches(specifier, input),
+        "WebFetch" => domain_matches(specifier, input),
+        t if READ_TOOLS.contains(&t) || EDIT_TOOLS.contains(&t) => path_matches(specifier, input, ctx),
+        _ => specifier == input,
+    })
+}
+
+/// Splits a shell command on `&&`, `||`, `;`, `|` and newlines, ignoring quoted text.
+fn split_command(command: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => { quote = None; current.push(c); }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => { quote = Some(c); current.push(c); }
+            (None, ';' | '\n') => parts.push(std::mem::take(&mut current)),
+            (None, '&' | '|') => {
+                if chars.peek() == Some(&c) {
+                    chars.next();
+                } else if c == '&' {
+                    current.push(c);
+                    continue;
+                }
+                parts.push(std::mem::take(&mut current));
+            }
+            (None, c) => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    parts.into_iter()
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+struct Outcome {
+    decision: &'static str,
+    rule: Option<(&'static str, String)>,
+    reason: String,
+}
+
+fn decision_rank(decision: &str) -> u8 {
+    match decision {
+        "deny" => 2,
+        "ask" => 1,
+        _ => 0,
+    }
+}
+
+// 没有规则命中时由 defaultMode 决定
+fn fallback(tool: &str, default_mode: &str) -> (&'static str, String) {
+    match default_mode {
+        "bypassPermissions" => ("allow", "No rule matched and defaultMode is bypassPermissions".to_string()),
+        _ if READ_ONLY_TOOLS.contains(&tool) => ("allow", format!("No rule matched and {} is read-only", tool)),
+        "acceptEdits" if EDIT_TOOLS.contains(&tool) => ("allow", "No rule matched and defaultMode is acceptEdits".to_string()),
+        "plan" => ("deny", format!("No rule matched and {} is not available in plan mode", tool)),
+        "dontAsk" => ("deny", "No rule matched and defaultMode is dontAsk".to_string()),
+        _ => ("ask", "No rule matched, so Claude Code will prompt".to_string()),
+    }
+}
+
+/// Checks one invocation against the lists in precedence order: deny, then ask, then allow.
+fn evaluate(
+    permissions: &PermissionsConfig,
+    tool: &str,
+    input: Option<&str>,
+    default_mode: &str,
+    ctx: &MatchContext,
+    skipped: &mut Vec<String>,
+) -> Outcome {
+    let lists: [(&'static str, &Option<Vec<String>>); 3] = [
+        ("deny", &permissions.deny),
+        ("ask", &permissions.ask),
+        ("allow", &permissions.allow),
+    ];
+
+    for (list, rules) in lists {
+        for rule in rules.iter().flatten() {
+            match rule_matches(rule, tool, input, ctx) {
+                Ok(true) => {
+                    let subject = input.map_or_else(|| tool.to_string(), |i| format!("'{}'", i));
+                    return Outcome {
+                        decision: list,
+                        rule: Some((list, rule.clone())),
+                        reason: format!("{} matches {} rule '{}'", subject, list, rule),
+                    };
+                }
+                Ok(false) => {}
+                Err(_) if !skipped.contains(rule) => skipped.push(rule.clone()),
+                Err(_) => {}
+            }
+        }
+    }
+
+    let (decision, reason) = fallback(tool, default_mode);
+    Outcome { decision, rule: None, reason }
+}
+
+/// Evaluates a whole invocation. Each part of a compound `Bash` command must pass on its
+/// own, and the strictest decision wins.
+fn decide(
+    permissions: &PermissionsConfig,
+    tool: &str,
+    input: Option<String>,
+    default_mode: &str,
+    ctx: &MatchContext,
+    skipped: &mut Vec<String>,
+) -> Outcome {
+    let invocations = match (&input, tool) {
+        (Some(command), "Bash") => split_command(command).into_iter().map(Some).collect(),
+        _ => vec![input],
+    };
+
+    let mut outcome: Option<Outcome> = None;
+    for invocation in &invocations {
+        let next = evaluate(permissions, tool, invocation.as_deref(), default_mode, ctx, skipped);
+        if outcome.as_ref().is_none_or(|o| decision_rank(next.decision) > decision_rank(o.decision)) {
+            outcome = Some(next);
+        }
+    }
+    outcome.unwrap_or_else(|| {
+        let (decision, reason) = fallback(tool, default_mode);
+        Outcome { decision, rule: None, reason }
+    })
+}
+
+fn project_root(settings_file: &Path) -> PathBuf {
+    let dir = settings_file.parent().unwrap_or(Path::new("/"));
+    // <project>/.claude/settings.json 的项目根目录是 .claude 的上一级
+    match dir.file_name() {
+        Some(name) if name == ".claude" => dir.parent().unwrap_or(dir).to_path_buf(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Evaluates a hypothetical tool call against the permission rules of the configured settings
+/// file (or `config_path`). `input` is the command for `Bash`, the file path for file tools and
+/// the URL for `WebFetch`; relative paths resolve against `cwd`, which defaults to the project root.
+#[tauri::command]
+pub async fn simulate_permission(
+    app: AppHandle,
+    config_path: Option<String>,
+    tool: String,
+    input: Option<String>,
+    cwd: Option<String>,
+) -> Result<PermissionCheckResult, String> {
+    let tool = tool.trim().to_string();
+    if tool.is_empty() {
+        return Err("Tool name must not be empty".to_string());
+    }
+
+    let settings_file = resolve_settings_file(&app, config_path).await?;
+    let permissions = permissions_of(&read_settings(&settings_file)?)?;
+    let default_mode = permissions.default_mode.clone().unwrap_or_else(|| "default".to_string());
+
+    let project_root = project_root(&settings_file);
+    let ctx = MatchContext {
+        cwd: match cwd.filter(|c| !c.trim().is_empty()) {
+            Some(cwd) => project_root.join(crate::commands::config::expand_home_path(&cwd)?),
+            None => project_root.clone(),
+        },
+        project_root,
+        home: dirs::home_dir(),
+    };
+
+    let input = input.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+    let mut skipped_rules = Vec::new();
+    let outcome = decide(&permissions, &tool, input, &default_mode, &ctx, &mut skipped_rules);
+
+    Ok(PermissionCheckResult {
+        decision: outcome.decision.to_string(),
+        matched_list: outcome.rule.as_ref().map(|(list, _)| list.to_string()),
+        matched_rule: outcome.rule.map(|(_, rule)| rule),
+        default_mode,
+        reason: outcome.reason,
+        skipped_rules,
+        settings_file: settings_file.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wildcard(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        wildcard_match(&pattern, &text)
+    }
+
+    fn segments(path: &str) -> Vec<String> {
+        path.split('/').map(|s| s.to_string()).collect()
+    }
+
+    fn ctx() -> MatchContext {
+        MatchContext {
+            cwd: PathBuf::from("/work/app/src"),
+            project_root: PathBuf::from("/work/app"),
+            home: Some(PathBuf::from("/home/dev")),
+        }
+    }
+
+    fn rules(rules: &[&str]) -> Option<Vec<String>> {
+        Some(rules.iter().map(|r| r.to_string()).collect())
+    }
+
+    fn decision(permissions: &PermissionsConfig, tool: &str, input: &str) -> (&'static str, Option<String>) {
+        let outcome = decide(permissions, tool, Some(input.to_string()), "default", &ctx(), &mut Vec::new());
+        (outcome.decision, outcome.rule.map(|(_, rule)| rule))
+    }
+
+    #[test]
+    fn wildcards_match_within_a_segment() {
+        assert!(wildcard("*.rs", "main.rs"));
+        assert!(wildcard("*", ""));
+        assert!(wildcard("a*b*c", "aXXbYYc"));
+        assert!(wildcard("a*b*c", "abcbc"));
+        assert!(wildcard("file?.txt", "file1.txt"));
+        assert!(wildcard("**", "anything"));
+        assert!(!wildcard("*.rs", "main.rs.bak"));
+        assert!(!wildcard("file?.txt", "file.txt"));
+        assert!(!wildcard("a*b", "acd"));
+        assert!(!wildcard("", "a"));
+    }
+
+    #[test]
+    fn long_wildcard_patterns_do_not_blow_up() {
+        let text = "a".repeat(200);
+        let pattern = format!("{}b", "*a".repeat(30));
+        assert!(!wildcard(&pattern, &text));
+    }
+
+    #[test]
+    fn double_star_spans_any_number_of_segments() {
+        assert!(segments_match(&segments("src/**/*.rs"), &segments("src/main.rs")));
+        assert!(segments_match(&segments("src/**/*.rs"), &segments("src/a/b/lib.rs")));
+        assert!(segments_match(&segments("**/.env"), &segments(".env")));
+        assert!(!segments_match(&segments("src/*.rs"), &segments("src/a/lib.rs")));
+        assert!(!segments_match(&segments("src/**/*.rs"), &segments("tests/lib.rs")));
+    }
+
+    #[test]
+    fn path_prefixes_choose_the_base_directory() {
+        let ctx = ctx();
+        // `//` 是文件系统绝对路径
+        assert!(path_matches("//etc/passwd", "/etc/passwd", &ctx));
+        // `~/` 是用户主目录
+        assert!(path_matches("~/.ssh/**", "~/.ssh/id_rsa", &ctx));
+        assert!(path_matches("~/.ssh/**", "/home/dev/.ssh/config", &ctx));
+        // `/` 是项目根目录，而不是文件系统根目录
+        assert!(path_matches("/docs/**", "/work/app/docs/guide.md", &ctx));
+        assert!(!path_matches("/docs/**", "/docs/guide.md", &ctx));
+        // 其余路径相对于 cwd
+        assert!(path_matches("./lib/*.rs", "lib/mod.rs", &ctx));
+        assert!(!path_matches("./lib/*.rs", "/work/app/lib/mod.rs", &ctx));
+    }
+
+    #[test]
+    fn bare_names_match_at_any_depth_and_directories_cover_their_files() {
+        let ctx = ctx();
+        assert!(path_matches(".env", ".env", &ctx));
+        assert!(path_matches(".env", "config/.env", &ctx));
+        assert!(!path_matches(".env", ".env.example", &ctx));
+        assert!(path_matches("secrets", "secrets/key.pem", &ctx));
+        assert!(path_matches("./build", "build/out/app.js", &ctx));
+    }
+
+    #[test]
+    fn commands_split_on_operators_outside_quotes() {
+        assert_eq!(split_command("npm test && git push"), ["npm test", "git push"]);
+        assert_eq!(split_command("a || b; c | d\ne"), ["a", "b", "c", "d", "e"]);
+        assert_eq!(split_command("echo 'a && b' ; ls"), ["echo 'a && b'", "ls"]);
+        assert_eq!(split_command("echo \"x | y\""), ["echo \"x | y\""]);
+        assert_eq!(split_command("sleep 1 & echo done"), ["sleep 1 & echo done"]);
+        assert_eq!(split_command("ls\npwd"), ["ls", "pwd"]);
+        assert!(split_command(" ; ").is_empty());
+    }
+
+    #[test]
+    fn bash_rules_match_exactly_or_by_prefix() {
+        assert!(bash_matches("npm run:*", "npm run build"));
+        assert!(bash_matches("npm run:*", "npm run"));
+        assert!(!bash_matches("npm run:*", "npm test"));
+        assert!(!bash_matches("npm run:*", "npm runner"));
+        assert!(!bash_matches("git:*", "gitk --all"));
+        assert!(bash_matches("git :*", "git log"));
+        assert!(bash_matches("git status", "git status"));
+        assert!(!bash_matches("git status", "git status --short"));
+    }
+
+    #[test]
+    fn domain_rules_match_hosts_and_subdomains() {
+        assert!(domain_matches("domain:example.com", "https://example.com/docs"));
+        assert!(domain_matches("domain:example.com", "example.com"));
+        assert!(!domain_matches("domain:example.com", "https://api.example.com"));
+        assert!(domain_matches("domain:*.example.com", "https://api.example.com/v1"));
+        assert!(!domain_matches("domain:*.example.com", "https://example.com"));
+        assert!(!domain_matches("domain:*.example.com", "https://badexample.com"));
+        assert!(!domain_matches("example.com", "https://example.com"));
+    }
+
+    #[test]
+    fn deny_beats_ask_beats_allow() {
+        let permissions = PermissionsConfig {
+            allow: rules(&["Bash(git:*)", "Read"]),
+            ask: rules(&["Bash(git push:*)"]),
+            deny: rules(&["Bash(git push --force:*)", "Read(.env)"]),
+            ..Default::default()
+        };
+
+        assert_eq!(decision(&permissions, "Bash", "git status"), ("allow", Some("Bash(git:*)".to_string())));
+        assert_eq!(decision(&permissions, "Bash", "git push origin"), ("ask", Some("Bash(git push:*)".to_string())));
+        assert_eq!(decision(&permissions, "Bash", "git push --force"), ("deny", Some("Bash(git push --force:*)".to_string())));
+        assert_eq!(decision(&permissions, "Read", "config/.env"), ("deny", Some("Read(.env)".to_string())));
+        assert_eq!(decision(&permissions, "Grep", "main.rs").0, "allow");
+    }
+
+    #[test]
+    fn every_part_of_a_compound_command_must_pass() {
+        let permissions = PermissionsConfig {
+            allow: rules(&["Bash(npm run:*)", "Bash(git status)"]),
+            deny: rules(&["Bash(rm:*)"]),
+            ..Default::default()
+        };
+
+        assert_eq!(decision(&permissions, "Bash", "npm run build && git status").0, "allow");
+        assert_eq!(decision(&permissions, "Bash", "npm run build && curl example.com").0, "ask");
+        assert_eq!(decision(&permissions, "Bash", "git status; rm -rf /"), ("deny", Some("Bash(rm:*)".to_string())));
+        // 引号里的分隔符不拆分命令
+        assert_eq!(decision(&permissions, "Bash", "npm run 'a; rm -rf /'").0, "allow");
+    }
+}
@@ -0,0 +1,347 @@
+// src-tauri/src/commands/permissions.rs
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::config::expand_home_path;
+use crate::models::PermissionsConfig;
+
+pub const RULE_LISTS: [&str; 3] = ["allow", "deny", "ask"];
+pub const DEFAULT_MODES: [&str; 5] = ["default", "acceptEdits", "plan", "dontAsk", "bypassPermissions"];
+
+// 这些工具的规则参数是 gitignore 风格的路径模式
+const PATH_TOOLS: [&str; 8] = ["Read", "Edit", "Write", "MultiEdit", "NotebookEdit", "Glob", "Grep", "LS"];
+
+/// Splits a rule into its tool name and optional specifier, e.g. `Bash(npm run test:*)`
+/// into `("Bash", Some("npm run test:*"))`.
+pub fn parse_rule(rule: &str) -> Result<(&str, Option<&str>), String> {
+    let (tool, specifier) = match rule.find('(') {
+        Some(open) => {
+            let specifier = rule[open + 1..]
+                .strip_suffix(')')
+                .ok_or_else(|| format!("Rule '{}' is missing a closing ')'", rule))?;
+            (&rule[..open], Some(specifier))
+        }
+        None if rule.contains(')') => return Err(format!("Rule '{}' has an unmatched ')'", rule)),
+        None => (rule, None),
+    };
+
+    if tool.is_empty() {
+        return Err(format!("Rule '{}' has no tool name", rule));
+    }
+    if !tool.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("'{}' is not a valid tool name", tool));
+    }
+    if specifier.is_some_and(|s| s.trim().is_empty()) {
+        return Err(format!("Rule '{}' has an empty specifier; use '{}' to match every call", rule, tool));
+    }
+
+    Ok((tool, specifier))
+}
+
+/// Checks a rule against Claude Code's permission rule grammar.
+pub fn validate_rule(rule: &str) -> Result<(), String> {
+    if rule.trim() != rule {
+        return Err(format!("Rule '{}' must not start or end with whitespace", rule));
+    }
+    let (tool, specifier) = parse_rule(rule)?;
+
+    // MCP 工具规则只能是 mcp__server 或 mcp__server__tool，不支持参数和通配符
+    if let Some(rest) = tool.strip_prefix("mcp__") {
+        if specifier.is_some() {
+            return Err(format!("MCP rule '{}' does not take a specifier", rule));
+        }
+        if rest.is_empty() || rest.split("__").any(|part| part.is_empty()) {
+            return Err(format!("MCP rule '{}' must be 'mcp__server' or 'mcp__server__tool'", rule));
+        }
+        return Ok(());
+    }
+
+    if !tool.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return Err(format!("Tool name '{}' must start with an uppercase letter", tool));
+    }
+
+    let Some(specifier) = specifier else { return Ok(()) };
+    match tool {
+        // `:*` 只能出现在末尾，表示前缀匹配
+        "Bash" if specifier.trim_end_matches(":*").contains(":*") => {
+            Err(format!("Rule '{}' may only use ':*' at the end of the command", rule))
+        }
+        "WebFetch" => {
+            let domain = specifier.strip_prefix("domain:")
+                .ok_or_else(|| format!("WebFetch rule '{}' must look like 'WebFetch(domain:example.com)'", rule))?;
+            if domain.is_empty() || domain.contains(|c: char| c == '/' || c.is_whitespace()) {
+                return Err(format!("'{}' is not a valid domain", domain));
+            }
+            Ok(())
+        }
+        tool if PATH_TOOLS.contains(&tool) && specifier.contains('\n') => {
+            Err(format!("Path pattern in '{}' must be a single line", rule))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_list(list: &str) -> Result<&str, String> {
+    RULE_LISTS.iter()
+        .find(|l| **l == list)
+        .copied()
+        .ok_or_else(|| format!("Unknown permission list '{}', expected one of: {}", list, RULE_LISTS.join(", ")))
+}
+
+fn check_index(rules: &[serde_json::Value], list: &str, index: usize) -> Result<(), String> {
+    if index >= rules.len() {
+        return Err(format!("Rule index {} is out of range ({} has {} rules)", index, list, rules.len()));
+    }
+    Ok(())
+}
+
+/// The configured settings file, or `config_path` when one is given.
+pub async fn resolve_settings_file(app: &AppHandle, config_path: Option<String>) -> Result<PathBuf, String> {
+    let config_path = match config_path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => super::config_path::get_config_path(app.clone()).await?,
+    };
+    expand_home_path(&config_path)
+}
+
+pub fn read_settings(settings_file: &Path) -> Result<serde_json::Value, String> {
+    if !settings_file.exists() {
+        return Ok(serde_json::json!({}));
+    }
+    let content = fs::read_to_string(settings_file)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let settings: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings file: {}", e))?;
+    if !settings.is_object() {
+        return Err("Settings file must contain a JSON object".to_string());
+    }
+    Ok(settings)
+}
+
+fn write_settings(settings_file: &Path, settings: &serde_json::Value) -> Result<(), String> {
+    if let Some(parent) = settings_file.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(settings_file, content).map_err(|e| format!("Failed to write settings file: {}", e))
+}
+
+pub fn permissions_of(settings: &serde_json::Value) -> Result<PermissionsConfig, String> {
+    match settings.get("permissions") {
+        Some(permissions) => serde_json::from_value(permissions.clone())
+            .map_err(|e| format!("Invalid permissions block: {}", e)),
+        None => Ok(PermissionsConfig::default()),
+    }
+}
+
+fn permissions_mut(settings: &mut serde_json::Value) -> Result<&mut serde_json::Map<String, serde_json::Value>, String> {
+    settings.as_object_mut()
+        .ok_or("Settings file must contain a JSON object")?
+        .entry("permissions")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or_else(|| "'permissions' must be an object".to_string())
+}
+
+fn rules_mut<'a>(
+    permissions: &'a mut serde_json::Map<String, serde_json::Value>,
+    list: &str,
+) -> Result<&'a mut Vec<serde_json::Value>, String> {
+    permissions.entry(list)
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or_else(|| format!("'permissions.{}' must be an array", list))
+}
+
+/// Adds the preset's rules that a settings file does not have yet and, if the preset sets one,
+/// its `defaultMode`. Existing rules keep their order.
+pub fn merge_permissions(settings_file: &Path, preset: &PermissionsConfig) -> Result<PermissionsConfig, String> {
+    let lists = [("allow", &preset.allow), ("deny", &preset.deny), ("ask", &preset.ask)];
+    for (_, rules) in lists {
+        for rule in rules.iter().flatten() {
+            validate_rule(rule)?;
+        }
+    }
+    if let Some(mode) = &preset.default_mode {
+        if !DEFAULT_MODES.contains(&mode.as_str()) {
+            return Err(format!("Unknown default mode '{}'", mode));
+        }
+    }
+
+    let mut settings = read_settings(settings_file)?;
+    let permissions = permissions_mut(&mut settings)?;
+    for (list, preset_rules) in lists {
+        let Some(preset_rules) = preset_rules.as_ref().filter(|r| !r.is_empty()) else { continue };
+        let rules = rules_mut(permissions, list)?;
+        for rule in preset_rules {
+            if !rules.iter().any(|r| r.as_str() == Some(rule.as_str())) {
+                rules.push(serde_json::Value::String(rule.clone()));
+            }
+        }
+    }
+    if let Some(mode) = &preset.default_mode {
+        permissions.insert("defaultMode".to_string(), serde_json::Value::String(mode.clone()));
+    }
+
+    write_settings(settings_file, &settings)?;
+    permissions_of(&settings)
+}
+
+/// Reads the settings file, applies `edit` to its `permissions` object and writes it back.
+/// Everything outside of `permissions` is left as it was.
+async fn edit_permissions<F>(app: &AppHandle, config_path: Option<String>, edit: F) -> Result<PermissionsConfig, String>
+where
+    F: FnOnce(&mut serde_json::Map<String, serde_json::Value>) -> Result<(), String>,
+{
+    let settings_file = resolve_settings_file(app, config_path).await?;
+    let mut settings = read_settings(&settings_file)?;
+    edit(permissions_mut(&mut settings)?)?;
+    write_settings(&settings_file, &settings)?;
+    permissions_of(&settings)
+}
+
+#[tauri::command]
+pub async fn get_permissions(app: AppHandle, config_path: Option<String>) -> Result<PermissionsConfig, String> {
+    let settings_file = resolve_settings_file(&app, config_path).await?;
+    permissions_of(&read_settings(&settings_file)?)
+}
+
+/// Returns the rule with surrounding whitespace removed if it is valid.
+#[tauri::command]
+pub async fn validate_permission_rule(rule: String) -> Result<String, String> {
+    let rule = rule.trim().to_string();
+    validate_rule(&rule)?;
+    Ok(rule)
+}
+
+/// Adds a rule to `list` (`allow`, `deny` or `ask`), at `position` or at the end.
+#[tauri::command]
+pub async fn add_permission_rule(
+    app: AppHandle,
+    config_path: Option<String>,
+    list: String,
+    rule: String,
+    position: Option<usize>,
+) -> Result<PermissionsConfig, String> {
+    let list = check_list(&list)?;
+    let rule = rule.trim().to_string();
+    validate_rule(&rule)?;
+
+    edit_permissions(&app, config_path, |permissions| {
+        let rules = rules_mut(permissions, list)?;
+        if rules.iter().any(|r| r.as_str() == Some(rule.as_str())) {
+            return Err(format!("'{}' is already in the {} list", rule, list));
+        }
+        let position = position.unwrap_or(rules.len()).min(rules.len());
+        rules.insert(position, serde_json::Value::String(rule));
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+pub async fn remove_permission_rule(
+    app: AppHandle,
+    config_path: Option<String>,
+    list: String,
+    index: usize,
+) -> Result<PermissionsConfig, String> {
+    let list = check_list(&list)?;
+    edit_permissions(&app, config_path, |permissions| {
+        let rules = rules_mut(permissions, list)?;
+        check_index(rules, list, index)?;
+        rules.remove(index);
+        Ok(())
+    }).await
+}
+
+/// Moves the rule at `from` so that it ends up at `to`.
+#[tauri::command]
+pub async fn reorder_permission_rule(
+    app: AppHandle,
+    config_path: Option<String>,
+    list: String,
+    from: usize,
+    to: usize,
+) -> Result<PermissionsConfig, String> {
+    let list = check_list(&list)?;
+    edit_permissions(&app, config_path, |permissions| {
+        let rules = rules_mut(permissions, list)?;
+        check_index(rules, list, from)?;
+        check_index(rules, list, to)?;
+        let rule = rules.remove(from);
+        rules.insert(to, rule);
+        Ok(())
+    }).await
+}
+
+/// Sets `permissions.defaultMode`; `None` removes it so Claude Code falls back to its default.
+#[tauri::command]
+pub async fn set_permission_default_mode(
+    app: AppHandle,
+    config_path: Option<String>,
+    mode: Option<String>,
+) -> Result<PermissionsConfig, String> {
+    if let Some(mode) = &mode {
+        if !DEFAULT_MODES.contains(&mode.as_str()) {
+            return Err(format!("Unknown default mode '{}', expected one of: {}", mode, DEFAULT_MODES.join(", ")));
+        }
+    }
+
+    edit_permissions(&app, config_path, |permissions| {
+        match mode {
+            Some(mode) => { permissions.insert("defaultMode".to_string(), serde_json::Value::String(mode)); }
+            None => { permissions.shift_remove("defaultMode"); }
+        }
+        Ok(())
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_rules_claude_code_understands() {
+        for rule in [
+            "Bash",
+            "Bash(npm run test:*)",
+            "Bash(git diff)",
+            "Read(./.env)",
+            "Read(~/.ssh/**)",
+            "Edit(//tmp/scratch.txt)",
+            "WebFetch(domain:example.com)",
+            "WebFetch(domain:*.example.com)",
+            "mcp__github",
+            "mcp__github__create_issue",
+            "Task",
+        ] {
+            assert_eq!(validate_rule(rule), Ok(()), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for rule in [
+            "",
+            " Bash",
+            "Bash(npm run test:*",
+            "Bash)",
+            "Bash()",
+            "(npm test)",
+            "bash(ls)",
+            "Bash tool",
+            "Bash(npm:* run)",
+            "WebFetch(example.com)",
+            "WebFetch(domain:)",
+            "WebFetch(domain:example.com/path)",
+            "mcp__",
+            "mcp__github__",
+            "mcp__github(create_issue)",
+            "Read(src/\n**)",
+        ] {
+            assert!(validate_rule(rule).is_err(), "{:?} should be rejected", rule);
+        }
+    }
+}
@@ -88,13 +88,12 @@ pub fn get_projects(app: AppHandle) -> Result<Vec<Project>, String> {
 
 #[tauri::command]
 pub fn get_project_by_id(app: AppHandle, id: String) -> Result<Option<Project>, String> {
-    get_project_by_id_internal(&app, &id)
+    let conn = get_database_connection(&app)
+        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    get_project_by_id_internal(&conn, &id)
 }
 
-fn get_project_by_id_internal(app: &AppHandle, id: &str) -> Result<Option<Project>, String> {
-    let conn = get_database_connection(app)
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-    
+pub fn get_project_by_id_internal(conn: &Connection, id: &str) -> Result<Option<Project>, String> {
     let mut stmt = conn
         .prepare("SELECT id, name, path, category, frameworks, project_type, description, scan_time, created_at, updated_at FROM projects WHERE id = ?1")
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
@@ -126,7 +125,7 @@ pub fn update_project(app: AppHandle, id: String, request: UpdateProjectRequest)
     let conn = get_database_connection(&app)
         .map_err(|e| format!("Failed to get database connection: {}", e))?;
     
-    let existing_project = get_project_by_id_internal(&app, &id)?;
+    let existing_project = get_project_by_id_internal(&conn, &id)?;
     if existing_project.is_none() {
         return Ok(None);
     }
@@ -0,0 +1,356 @@
+// src-tauri/src/commands/project_settings.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use rusqlite::OptionalExtension;
+use crate::commands::api_keys::{row_to_api_key, API_KEY_COLUMNS};
+use crate::commands::config::{expand_home_path, write_env_to_settings};
+use crate::commands::permissions::merge_permissions;
+use crate::commands::settings_schema::check_settings;
+use crate::commands::project_db::{get_project_by_id_internal, get_projects_by_category};
+use crate::crypto;
+use crate::db;
+use crate::models::{PermissionsConfig, Project};
+
+const LOCAL_SETTINGS_IGNORE: &str = ".claude/settings.local.json";
+const SCOPES: [&str; 2] = ["project", "local"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectSettingsFile {
+    pub project_id: String,
+    /// `project` for `.claude/settings.json`, `local` for `.claude/settings.local.json`.
+    pub scope: String,
+    pub path: String,
+    pub exists: bool,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryApplyResult {
+    pub project_id: String,
+    pub project_name: String,
+    pub path: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+fn settings_path(project: &Project, scope: &str) -> Result<PathBuf, String> {
+    let file_name = match scope {
+        "project" => "settings.json",
+        "local" => "settings.local.json",
+        other => return Err(format!("Unknown settings scope '{}', expected 'project' or 'local'", other)),
+    };
+    Ok(expand_home_path(&project.path)?.join(".claude").join(file_name))
+}
+
+fn load_project(app: &AppHandle, project_id: &str) -> Result<Project, String> {
+    let conn = db::get_database_connection(app).map_err(|e| e.to_string())?;
+    get_project_by_id_internal(&conn, project_id)?
+        .ok_or_else(|| format!("Project '{}' not found", project_id))
+}
+
+fn read_settings_file(project: &Project, scope: &str) -> Result<ProjectSettingsFile, String> {
+    let path = settings_path(project, scope)?;
+    let exists = path.exists();
+    let content = if exists {
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?
+    } else {
+        String::new()
+    };
+
+    Ok(ProjectSettingsFile {
+        project_id: project.id.clone(),
+        scope: scope.to_string(),
+        path: path.to_string_lossy().to_string(),
+        exists,
+        content,
+    })
+}
+
+/// Claude Code 创建 settings.local.json 时会让 git 忽略它，这里保持一致，避免密钥被提交
+fn ensure_local_settings_ignored(project: &Project) -> Result<(), String> {
+    let project_path = expand_home_path(&project.path)?;
+    if !project_path.join(".git").exists() {
+        return Ok(());
+    }
+
+    let gitignore = project_path.join(".gitignore");
+    let existing = fs::read_to_string(&gitignore).unwrap_or_default();
+    if existing.lines().any(|line| line.trim().trim_start_matches('/') == LOCAL_SETTINGS_IGNORE) {
+        return Ok(());
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(LOCAL_SETTINGS_IGNORE);
+    content.push('\n');
+    fs::write(&gitignore, content).map_err(|e| format!("Failed to update .gitignore: {}", e))
+}
+
+fn prepare_settings_file(project: &Project, scope: &str) -> Result<PathBuf, String> {
+    let path = settings_path(project, scope)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    if scope == "local" && !path.exists() {
+        ensure_local_settings_ignored(project)?;
+    }
+    Ok(path)
+}
+
+fn apply_to_category<F>(app: &AppHandle, category: String, scope: &str, apply: F) -> Result<Vec<CategoryApplyResult>, String>
+where
+    F: Fn(&Path) -> Result<(), String>,
+{
+    if !SCOPES.contains(&scope) {
+        return Err(format!("Unknown settings scope '{}', expected 'project' or 'local'", scope));
+    }
+    let projects = get_projects_by_category(app.clone(), category.clone())?;
+    if projects.is_empty() {
+        return Err(format!("No projects in category '{}'", category));
+    }
+    Ok(apply_to_projects(&projects, scope, apply))
+}
+
+/// 单个项目失败不影响其他项目，结果逐个返回
+fn apply_to_projects<F>(projects: &[Project], scope: &str, apply: F) -> Vec<CategoryApplyResult>
+where
+    F: Fn(&Path) -> Result<(), String>,
+{
+    projects.iter()
+        .map(|project| {
+            let result = prepare_settings_file(project, scope).and_then(|path| apply(&path).map(|_| path));
+            CategoryApplyResult {
+                project_id: project.id.clone(),
+                project_name: project.name.clone(),
+                path: settings_path(project, scope).map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+                success: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_project_settings(app: AppHandle, project_id: String, scope: String) -> Result<ProjectSettingsFile, String> {
+    read_settings_file(&load_project(&app, &project_id)?, &scope)
+}
+
+/// Creates an empty settings file for the project. Fails if the file already exists.
+#[tauri::command]
+pub async fn create_project_settings(app: AppHandle, project_id: String, scope: String) -> Result<ProjectSettingsFile, String> {
+    let project = load_project(&app, &project_id)?;
+    if settings_path(&project, &scope)?.exists() {
+        return Err(format!("{} settings already exist for '{}'", scope, project.name));
+    }
+
+    let path = prepare_settings_file(&project, &scope)?;
+    fs::write(&path, "{}").map_err(|e| format!("Failed to write file: {}", e))?;
+    read_settings_file(&project, &scope)
+}
+
+#[tauri::command]
+pub async fn save_project_settings(
+    app: AppHandle,
+    project_id: String,
+    scope: String,
+    content: String,
+) -> Result<ProjectSettingsFile, String> {
+    let project = load_project(&app, &project_id)?;
+    let parsed: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("无效的JSON格式: {}", e))?;
+    if !parsed.is_object() {
+        return Err("Settings must be a JSON object".to_string());
+    }
+    check_settings(&parsed)?;
+
+    let path = prepare_settings_file(&project, &scope)?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+    read_settings_file(&project, &scope)
+}
+
+/// Writes a stored API key into the settings of every project in `category`.
+/// Only the git-ignored `local` scope is accepted so the key is never committed with the project.
+/// Only the `env` block is changed; see [`write_env_to_settings`].
+#[tauri::command]
+pub async fn apply_api_key_to_category(
+    app: AppHandle,
+    category: String,
+    api_key_id: String,
+    scope: Option<String>,
+) -> Result<Vec<CategoryApplyResult>, String> {
+    let scope = scope.unwrap_or_else(|| "local".to_string());
+    check_secret_scope(&scope)?;
+    let api_key = {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        conn.query_row(
+            &format!("SELECT {} FROM api_keys WHERE id = ?1", API_KEY_COLUMNS),
+            [&api_key_id],
+            row_to_api_key,
+        ).optional().map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("API key '{}' not found", api_key_id))?
+    };
+    let secret = crypto::decrypt_secret(&api_key.anthropic_api_key)?;
+
+    let results = apply_to_category(&app, category, &scope, |path| {
+        write_env_to_settings(path, &secret, api_key.anthropic_base_url.clone())
+    })?;
+
+    if results.iter().any(|r| r.success) {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE api_keys SET last_used_at = ?1 WHERE id = ?2",
+            (chrono::Utc::now().to_rfc3339(), &api_key.id),
+        ).map_err(|e| e.to_string())?;
+    }
+
+    Ok(results)
+}
+
+/// 密钥只能写入 settings.local.json，settings.json 会随项目提交
+fn check_secret_scope(scope: &str) -> Result<(), String> {
+    match scope {
+        "local" => Ok(()),
+        "project" => Err("API keys can only be applied to the 'local' scope; '.claude/settings.json' is shared with the repository".to_string()),
+        other => Err(format!("Unknown settings scope '{}', expected 'local'", other)),
+    }
+}
+
+/// Adds a set of permission rules (and optionally a default mode) to every project in `category`.
+/// Defaults to the shared `project` scope.
+#[tauri::command]
+pub async fn apply_permission_preset_to_category(
+    app: AppHandle,
+    category: String,
+    preset: PermissionsConfig,
+    scope: Option<String>,
+) -> Result<Vec<CategoryApplyResult>, String> {
+    let scope = scope.unwrap_or_else(|| "project".to_string());
+    apply_to_category(&app, category, &scope, |path| merge_permissions(path, &preset).map(|_| ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_project(name: &str) -> Project {
+        let dir = std::env::temp_dir().join(format!("claude-meta-project-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        Project {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            path: dir.to_string_lossy().to_string(),
+            category: "test".to_string(),
+            frameworks: Vec::new(),
+            project_type: "rust".to_string(),
+            description: None,
+            scan_time: String::new(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn root(project: &Project) -> PathBuf {
+        PathBuf::from(&project.path)
+    }
+
+    #[test]
+    fn settings_path_maps_scopes_to_files() {
+        let project = temp_project("paths");
+        let claude_dir = root(&project).join(".claude");
+
+        assert_eq!(settings_path(&project, "project").unwrap(), claude_dir.join("settings.json"));
+        assert_eq!(settings_path(&project, "local").unwrap(), claude_dir.join("settings.local.json"));
+        assert!(settings_path(&project, "user").is_err());
+
+        fs::remove_dir_all(root(&project)).unwrap();
+    }
+
+    #[test]
+    fn local_settings_are_ignored_once() {
+        let project = temp_project("ignore");
+        fs::create_dir_all(root(&project).join(".git")).unwrap();
+        fs::write(root(&project).join(".gitignore"), "target").unwrap();
+
+        ensure_local_settings_ignored(&project).unwrap();
+        ensure_local_settings_ignored(&project).unwrap();
+
+        let gitignore = fs::read_to_string(root(&project).join(".gitignore")).unwrap();
+        assert_eq!(gitignore, format!("target\n{}\n", LOCAL_SETTINGS_IGNORE));
+
+        fs::remove_dir_all(root(&project)).unwrap();
+    }
+
+    #[test]
+    fn existing_ignore_entries_are_kept() {
+        let project = temp_project("existing");
+        fs::create_dir_all(root(&project).join(".git")).unwrap();
+        let original = format!("node_modules\n/{}\n", LOCAL_SETTINGS_IGNORE);
+        fs::write(root(&project).join(".gitignore"), &original).unwrap();
+
+        ensure_local_settings_ignored(&project).unwrap();
+
+        assert_eq!(fs::read_to_string(root(&project).join(".gitignore")).unwrap(), original);
+
+        fs::remove_dir_all(root(&project)).unwrap();
+    }
+
+    #[test]
+    fn missing_gitignore_is_created_only_in_git_repos() {
+        let repo = temp_project("repo");
+        fs::create_dir_all(root(&repo).join(".git")).unwrap();
+        ensure_local_settings_ignored(&repo).unwrap();
+        assert_eq!(
+            fs::read_to_string(root(&repo).join(".gitignore")).unwrap(),
+            format!("{}\n", LOCAL_SETTINGS_IGNORE)
+        );
+
+        let plain = temp_project("plain");
+        ensure_local_settings_ignored(&plain).unwrap();
+        assert!(!root(&plain).join(".gitignore").exists());
+
+        fs::remove_dir_all(root(&repo)).unwrap();
+        fs::remove_dir_all(root(&plain)).unwrap();
+    }
+
+    #[test]
+    fn results_are_reported_per_project() {
+        let ok = temp_project("ok");
+        let failing = temp_project("failing");
+        let projects = vec![ok.clone(), failing.clone()];
+
+        let results = apply_to_projects(&projects, "local", |path| {
+            if path.starts_with(root(&failing)) {
+                Err("boom".to_string())
+            } else {
+                fs::write(path, "{}").map_err(|e| e.to_string())
+            }
+        });
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].project_id, ok.id);
+        assert!(results[0].success);
+        assert_eq!(results[0].error, None);
+        assert!(root(&ok).join(".claude/settings.local.json").exists());
+        assert_eq!(results[1].project_name, "failing");
+        assert!(!results[1].success);
+        assert_eq!(results[1].error.as_deref(), Some("boom"));
+        assert_eq!(
+            results[1].path,
+            root(&failing).join(".claude/settings.local.json").to_string_lossy()
+        );
+
+        fs::remove_dir_all(root(&ok)).unwrap();
+        fs::remove_dir_all(root(&failing)).unwrap();
+    }
+
+    #[test]
+    fn secrets_are_only_applied_to_the_local_scope() {
+        assert!(check_secret_scope("local").is_ok());
+        assert!(check_secret_scope("project").is_err());
+        assert!(check_secret_scope("user").is_err());
+    }
+}
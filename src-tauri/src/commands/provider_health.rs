@@ -0,0 +1,201 @@
+// src-tauri/src/commands/provider_health.rs
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::task::JoinSet;
+use crate::commands::connectivity::{probe_endpoint, ConnectivityResult};
+use crate::commands::router::{load_router_config, mask_stored_key, resolve_provider_api_key, update_router_config};
+use crate::db;
+use crate::models::Provider;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderHealth {
+    pub name: String,
+    pub result: ConnectivityResult,
+    pub discovered_models: Vec<String>,
+    /// Models declared on the provider that the endpoint did not report.
+    pub unknown_models: Vec<String>,
+}
+
+/// Derives the OpenAI-compatible `/models` URL from a provider's `api_base_url`,
+/// which CCR expects to be the full chat completions endpoint.
+fn models_url(api_base_url: &str) -> String {
+    let base = api_base_url.trim_end_matches('/');
+    if base.ends_with("/models") {
+        return base.to_string();
+    }
+    let base = base
+        .strip_suffix("/chat/completions")
+        .or_else(|| base.strip_suffix("/completions"))
+        .unwrap_or(base);
+    format!("{}/models", base)
+}
+
+// OpenAI 风格返回 data[].id，Gemini 和 Ollama 返回 models[].name
+fn parse_model_ids(body: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return Vec::new();
+    };
+
+    let from_data = value["data"].as_array()
+        .map(|items| items.iter().filter_map(|m| m["id"].as_str()).collect::<Vec<_>>());
+    let from_models = || value["models"].as_array()
+        .map(|items| items.iter().filter_map(|m| m["name"].as_str().or_else(|| m["id"].as_str())).collect::<Vec<_>>());
+
+    from_data.or_else(from_models)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| id.strip_prefix("models/").unwrap_or(id).to_string())
+        .collect()
+}
+
+async fn check_provider(provider: Provider, api_key: String) -> ProviderHealth {
+    let url = models_url(&provider.api_base_url);
+
+    let mut headers = vec![("authorization", format!("Bearer {}", api_key))];
+    if url.contains("googleapis.com") {
+        headers.push(("x-goog-api-key", api_key));
+    }
+
+    let result = probe_endpoint(&url, &headers).await;
+    let discovered_models = result.body.as_deref().map(parse_model_ids).unwrap_or_default();
+    let unknown_models = if discovered_models.is_empty() {
+        Vec::new()
+    } else {
+        provider.models.iter().filter(|m| !discovered_models.contains(m)).cloned().collect()
+    };
+
+    ProviderHealth {
+        name: provider.name,
+        result,
+        discovered_models,
+        unknown_models,
+    }
+}
+
+/// Reports a provider whose key could not be resolved (e.g. a missing environment variable
+/// or a deleted stored key) without probing it.
+fn config_error(provider: Provider, message: String) -> ProviderHealth {
+    ProviderHealth {
+        result: ConnectivityResult {
+            url: models_url(&provider.api_base_url),
+            status: "config_error".to_string(),
+            http_status: None,
+            latency_ms: 0,
+            message: Some(message),
+            body: None,
+        },
+        name: provider.name,
+        discovered_models: Vec::new(),
+        unknown_models: Vec::new(),
+    }
+}
+
+#[tauri::command]
+pub async fn check_router_providers(app: AppHandle) -> Result<Vec<ProviderHealth>, String> {
+    let config = load_router_config(&app).await?;
+
+    let mut keyed_providers = Vec::new();
+    {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        // 单个提供商的密钥解析失败只标记它自己，不影响其他提供商的检查
+        for provider in config.providers {
+            let api_key = resolve_provider_api_key(&conn, &provider);
+            keyed_providers.push((provider, api_key));
+        }
+    }
+
+    let mut probes = JoinSet::new();
+    for (index, (provider, api_key)) in keyed_providers.into_iter().enumerate() {
+        probes.spawn(async move {
+            let health = match api_key {
+                Ok(api_key) => check_provider(provider, api_key).await,
+                Err(message) => config_error(provider, message),
+            };
+            (index, health)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = probes.join_next().await {
+        let (index, health) = joined.map_err(|e| format!("Provider check panicked: {}", e))?;
+        results.push((index, health));
+    }
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(results.into_iter().map(|(_, health)| health).collect())
+}
+
+/// Replaces a provider's model list. When `models` is omitted the list is rediscovered
+/// from the provider's `/models` endpoint.
+#[tauri::command]
+pub async fn sync_provider_models(app: AppHandle, provider_name: String, models: Option<Vec<String>>) -> Result<Provider, String> {
+    let mut config = load_router_config(&app).await?;
+    let provider = config.providers.iter_mut()
+        .find(|p| p.name == provider_name)
+        .ok_or_else(|| format!("Provider '{}' not found", provider_name))?;
+
+    let models = match models {
+        Some(models) => models,
+        None => {
+            let api_key = {
+                let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+                resolve_provider_api_key(&conn, provider)?
+            };
+            let health = check_provider(provider.clone(), api_key).await;
+            if health.result.status != "ok" {
+                return Err(format!(
+                    "Failed to discover models for '{}': {}",
+                    provider_name,
+                    health.result.message.unwrap_or(health.result.status)
+                ));
+            }
+            health.discovered_models
+        }
+    };
+
+    if models.is_empty() {
+        return Err(format!("No models to sync for provider '{}'", provider_name));
+    }
+
+    provider.models = models;
+    let mut updated = provider.clone();
+    update_router_config(app, config, None).await?;
+
+    updated.api_key = mask_stored_key(&updated.api_key)?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_models_url_from_chat_endpoint() {
+        assert_eq!(models_url("https://openrouter.ai/api/v1/chat/completions"), "https://openrouter.ai/api/v1/models");
+        assert_eq!(models_url("http://localhost:11434/v1/"), "http://localhost:11434/v1/models");
+        assert_eq!(models_url("https://api.example.com/v1/models"), "https://api.example.com/v1/models");
+    }
+
+    #[test]
+    fn parses_openai_and_gemini_model_lists() {
+        assert_eq!(parse_model_ids(r#"{"data":[{"id":"gpt-4o"},{"id":"o3"}]}"#), ["gpt-4o", "o3"]);
+        assert_eq!(parse_model_ids(r#"{"models":[{"name":"models/gemini-2.5-pro"}]}"#), ["gemini-2.5-pro"]);
+        assert!(parse_model_ids("not json").is_empty());
+    }
+
+    #[test]
+    fn unresolvable_key_marks_only_that_provider() {
+        let provider: Provider = serde_json::from_value(serde_json::json!({
+            "name": "deepseek",
+            "api_base_url": "https://api.deepseek.com/chat/completions",
+            "api_key": "$DEEPSEEK_API_KEY",
+        })).unwrap();
+        let health = config_error(provider, "Environment variable 'DEEPSEEK_API_KEY' used by provider 'deepseek' is not set".to_string());
+
+        assert_eq!(health.name, "deepseek");
+        assert_eq!(health.result.status, "config_error");
+        assert_eq!(health.result.url, "https://api.deepseek.com/models");
+        assert!(health.result.message.unwrap().contains("DEEPSEEK_API_KEY"));
+    }
+}
@@ -0,0 +1,224 @@
+// src-tauri/src/commands/provider_presets.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use crate::db;
+use crate::models::{Provider, ProviderPreset, ProviderPresetCatalog};
+
+const BUNDLED_PRESETS: &str = include_str!("../../presets/providers.json");
+const OVERRIDE_FILE_NAME: &str = "provider_presets.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderPresetList {
+    /// Version of the bundled catalog.
+    pub version: u32,
+    pub presets: Vec<ProviderPreset>,
+    /// Where user presets are read from; entries there replace bundled presets with the same id.
+    pub override_path: String,
+    pub override_loaded: bool,
+    pub override_error: Option<String>,
+}
+
+fn override_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(data_dir.join(OVERRIDE_FILE_NAME))
+}
+
+fn load_presets(app: &AppHandle) -> Result<ProviderPresetList, String> {
+    merge_presets(BUNDLED_PRESETS, &override_path(app)?)
+}
+
+/// The bundled catalog with the user presets at `path` applied on top.
+fn merge_presets(bundled: &str, path: &Path) -> Result<ProviderPresetList, String> {
+    let bundled: ProviderPresetCatalog = serde_json::from_str(bundled)
+        .map_err(|e| format!("Bundled provider presets are invalid: {}", e))?;
+
+    let mut list = ProviderPresetList {
+        version: bundled.version,
+        presets: bundled.presets,
+        override_path: path.to_string_lossy().to_string(),
+        override_loaded: false,
+        override_error: None,
+    };
+
+    if !path.exists() {
+        return Ok(list);
+    }
+
+    // 用户文件有问题时仍然返回内置预设，并把错误交给前端展示
+    let overrides = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str::<ProviderPresetCatalog>(&content).map_err(|e| e.to_string()));
+
+    match overrides {
+        Ok(overrides) => {
+            for preset in overrides.presets {
+                match list.presets.iter_mut().find(|p| p.id == preset.id) {
+                    Some(existing) => *existing = preset,
+                    None => list.presets.push(preset),
+                }
+            }
+            list.override_loaded = true;
+        }
+        Err(e) => list.override_error = Some(format!("Failed to load {}: {}", OVERRIDE_FILE_NAME, e)),
+    }
+
+    Ok(list)
+}
+
+fn build_provider(
+    preset: ProviderPreset,
+    api_key: Option<String>,
+    api_key_id: Option<String>,
+    name: Option<String>,
+    models: Option<Vec<String>>,
+) -> Result<Provider, String> {
+    let api_key = api_key.filter(|k| !k.trim().is_empty());
+    let api_key_id = api_key_id.filter(|id| !id.trim().is_empty());
+    // 引用已保存的密钥时，密钥在写出 config.json 时才解析
+    let api_key = match (api_key, &api_key_id) {
+        (Some(key), _) => key,
+        (None, Some(_)) => String::new(),
+        (None, None) => preset.default_api_key
+            .ok_or_else(|| format!("An API key is required for '{}'", preset.name))?,
+    };
+
+    Ok(Provider {
+        name: name.filter(|n| !n.trim().is_empty()).unwrap_or(preset.name),
+        api_base_url: preset.api_base_url,
+        api_key,
+        api_key_id,
+        models: models.filter(|m| !m.is_empty()).unwrap_or(preset.models),
+        transformer: preset.transformer,
+        extra: serde_json::Map::new(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_provider_presets(app: AppHandle) -> Result<ProviderPresetList, String> {
+    load_presets(&app)
+}
+
+/// Builds a `Provider` from a preset. The provider is returned, not saved. Pass `api_key_id`
+/// to use a stored API key instead of a literal `api_key`.
+#[tauri::command]
+pub async fn create_provider_from_preset(
+    app: AppHandle,
+    preset_id: String,
+    api_key: Option<String>,
+    api_key_id: Option<String>,
+    name: Option<String>,
+    models: Option<Vec<String>>,
+) -> Result<Provider, String> {
+    let preset = load_presets(&app)?
+        .presets
+        .into_iter()
+        .find(|p| p.id == preset_id)
+        .ok_or_else(|| format!("Provider preset '{}' not found", preset_id))?;
+
+    if let Some(id) = api_key_id.as_deref().filter(|id| !id.trim().is_empty()) {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        let exists: bool = conn.query_row("SELECT COUNT(*) > 0 FROM api_keys WHERE id = ?1", [id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("API key '{}' not found", id));
+        }
+    }
+
+    build_provider(preset, api_key, api_key_id, name, models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_override(content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("claude-meta-presets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(OVERRIDE_FILE_NAME);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn bundled(id: &str) -> ProviderPreset {
+        merge_presets(BUNDLED_PRESETS, Path::new("/nonexistent/provider_presets.json")).unwrap()
+            .presets.into_iter().find(|p| p.id == id).unwrap()
+    }
+
+    #[test]
+    fn bundled_presets_parse_with_unique_ids() {
+        let list = merge_presets(BUNDLED_PRESETS, Path::new("/nonexistent/provider_presets.json")).unwrap();
+        assert!(!list.presets.is_empty());
+        assert!(!list.override_loaded);
+
+        let mut ids: Vec<&str> = list.presets.iter().map(|p| p.id.as_str()).collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count, "duplicate preset ids in presets/providers.json");
+    }
+
+    #[test]
+    fn user_presets_replace_or_extend_bundled_ones_by_id() {
+        let path = temp_override(&serde_json::json!({
+            "version": 1,
+            "presets": [
+                { "id": "deepseek", "name": "deepseek-proxy", "api_base_url": "https://proxy.example.com/v1/chat/completions", "models": ["deepseek-chat"] },
+                { "id": "internal", "name": "internal", "api_base_url": "https://llm.example.com/v1/chat/completions", "models": ["m-1"] }
+            ]
+        }).to_string());
+
+        let bundled_count = merge_presets(BUNDLED_PRESETS, Path::new("/nonexistent/provider_presets.json")).unwrap().presets.len();
+        let list = merge_presets(BUNDLED_PRESETS, &path).unwrap();
+        assert!(list.override_loaded);
+        assert_eq!(list.presets.len(), bundled_count + 1);
+
+        let deepseek: Vec<&ProviderPreset> = list.presets.iter().filter(|p| p.id == "deepseek").collect();
+        assert_eq!(deepseek.len(), 1);
+        assert_eq!(deepseek[0].api_base_url, "https://proxy.example.com/v1/chat/completions");
+        assert_eq!(list.presets.last().unwrap().id, "internal");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn invalid_user_presets_fall_back_to_the_bundled_catalog() {
+        let path = temp_override("{ not json");
+        let list = merge_presets(BUNDLED_PRESETS, &path).unwrap();
+        assert!(!list.override_loaded);
+        assert!(list.override_error.is_some());
+        assert!(list.presets.iter().any(|p| p.id == "deepseek"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn providers_take_url_models_and_transformer_from_the_preset() {
+        let preset = bundled("deepseek");
+        let provider = build_provider(preset.clone(), Some("sk-deepseek".to_string()), None, None, None).unwrap();
+
+        assert_eq!(provider.name, "deepseek");
+        assert_eq!(provider.api_base_url, preset.api_base_url);
+        assert_eq!(provider.api_key, "sk-deepseek");
+        assert_eq!(provider.models, preset.models);
+        assert_eq!(
+            serde_json::to_value(&provider.transformer).unwrap(),
+            serde_json::to_value(&preset.transformer).unwrap()
+        );
+
+        let renamed = build_provider(preset, Some("sk-deepseek".to_string()), None, Some("ds".to_string()), Some(vec!["deepseek-chat".to_string()])).unwrap();
+        assert_eq!(renamed.name, "ds");
+        assert_eq!(renamed.models, ["deepseek-chat"]);
+    }
+
+    #[test]
+    fn api_keys_come_from_the_request_a_stored_key_or_the_preset_default() {
+        assert!(build_provider(bundled("deepseek"), None, None, None, None).is_err());
+        assert_eq!(build_provider(bundled("ollama"), None, None, None, None).unwrap().api_key, "ollama");
+
+        let stored = build_provider(bundled("deepseek"), None, Some("key-1".to_string()), None, None).unwrap();
+        assert_eq!(stored.api_key_id.as_deref(), Some("key-1"));
+        assert_eq!(stored.api_key, "");
+    }
+}
@@ -5,51 +5,14 @@ use tauri::Manager;
 use std::fs;
 use dirs;
 use crate::db;
+use crate::crypto;
+use crate::models::{ClaudeCodeRouterConfig, Provider, RouterConfig, Transformer};
+use crate::commands::router_validation::{check_routes};
 use std::path::PathBuf;
 use tokio::sync::oneshot;
-use rusqlite::OptionalExtension;
+use rusqlite::{Connection, OptionalExtension};
 use tauri_plugin_dialog::DialogExt;
 
-// Structs from lib.rs
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Transformer {
-    pub name: String,
-    pub args: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Provider {
-    pub name: String,
-    pub api_base_url: String,
-    pub api_key: String,
-    pub models: Vec<String>,
-    pub transformer: Option<Transformer>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct RouterConfig {
-    pub default: Option<String>,
-    pub background: Option<String>,
-    pub think: Option<String>,
-    pub long_context: Option<String>,
-    pub long_context_threshold: Option<u32>,
-    pub web_search: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ClaudeCodeRouterConfig {
-    pub anthropic_api_key: Option<String>,
-    pub proxy_url: Option<String>,
-    pub log: Option<bool>,
-    pub host: Option<String>,
-    pub non_interactive_mode: Option<bool>,
-    pub api_timeout_ms: Option<u32>,
-    pub custom_router_path: Option<String>,
-    pub providers: Vec<Provider>,
-    pub router: RouterConfig,
-    pub transformers: Option<Vec<Transformer>>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RouterBackupFile {
     pub filename: String,
@@ -64,6 +27,17 @@ fn get_router_config_path() -> std::path::PathBuf {
     home_dir.join(".claude-code-router").join("config.json")
 }
 
+fn default_router_config() -> ClaudeCodeRouterConfig {
+    ClaudeCodeRouterConfig {
+        api_timeout_ms: Some(600000),
+        router: RouterConfig {
+            long_context_threshold: Some(60000),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
 pub async fn get_router_config_path_with_custom(app: Option<&tauri::AppHandle>) -> Result<std::path::PathBuf, String> {
     if let Some(app_handle) = app {
         // 尝试从数据库获取自定义路径
@@ -81,51 +55,36 @@ pub async fn get_router_config_path_with_custom(app: Option<&tauri::AppHandle>)
     Ok(get_router_config_path())
 }
 
-#[tauri::command]
-pub async fn get_router_config(app: tauri::AppHandle) -> Result<ClaudeCodeRouterConfig, String> {
-    println!("=== GET_ROUTER_CONFIG CALLED ===");
-    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
-    
+fn load_router_config_from_db(conn: &Connection) -> Result<ClaudeCodeRouterConfig, String> {
     // 首先尝试从数据库读取配置
-    let mut config = ClaudeCodeRouterConfig {
-        anthropic_api_key: None,
-        proxy_url: None,
-        log: None,
-        host: None,
-        non_interactive_mode: None,
-        api_timeout_ms: Some(600000),
-        custom_router_path: None,
-        providers: Vec::new(),
-        router: RouterConfig {
-            default: None,
-            background: None,
-            think: None,
-            long_context: None,
-            long_context_threshold: Some(60000),
-            web_search: None,
-        },
-        transformers: None,
-    };
+    let mut config = default_router_config();
     
     // 从数据库读取提供商
     let providers_result = {
-        let mut stmt = conn.prepare("SELECT name, api_base_url, api_key, models, transformer FROM providers ORDER BY created_at DESC")
+        let mut stmt = conn.prepare("SELECT name, api_base_url, api_key, models, transformer, extra, api_key_id FROM providers ORDER BY position, rowid")
             .map_err(|e| e.to_string())?;
         
         let provider_rows = stmt.query_map([], |row| {
             let models_json: String = row.get(3)?;
             let transformer_json: Option<String> = row.get(4)?;
+            let extra_json: Option<String> = row.get(5)?;
             
             let models: Vec<String> = serde_json::from_str(&models_json).unwrap_or_default();
             let transformer: Option<Transformer> = transformer_json
-                .and_then(|json| serde_json::from_str(&json).ok());
+                .and_then(|json| serde_json::from_str::<Transformer>(&json).ok())
+                .map(|mut t| { t.normalize_legacy(); t });
+            let extra = extra_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
             
             Ok(Provider {
                 name: row.get(0)?,
                 api_base_url: row.get(1)?,
                 api_key: row.get(2)?,
+                api_key_id: row.get(6)?,
                 models,
                 transformer,
+                extra,
             })
         }).map_err(|e| e.to_string())?;
         
@@ -139,6 +98,17 @@ pub async fn get_router_config(app: tauri::AppHandle) -> Result<ClaudeCodeRouter
         providers
     };
     
+    // 其余顶层字段（LOG、HOST、transformers 以及未识别的字段）整体存放在 globals 中
+    if let Ok(Some(globals_json)) = conn.query_row(
+        "SELECT config_value FROM router_configs WHERE config_key = 'globals'",
+        [],
+        |row| row.get::<_, String>(0),
+    ).optional() {
+        if let Ok(stored) = serde_json::from_str::<ClaudeCodeRouterConfig>(&globals_json) {
+            config = ClaudeCodeRouterConfig { router: config.router, ..stored };
+        }
+    }
+    
     config.providers = providers_result;
     println!("Loaded {} providers from database", config.providers.len());
     
@@ -170,18 +140,30 @@ pub async fn get_router_config(app: tauri::AppHandle) -> Result<ClaudeCodeRouter
         config.proxy_url = Some(proxy_url);
     }
     
+    Ok(config)
+}
+
+/// The router config as stored, with secrets still encrypted. Commands that edit the config
+/// and save it back use this; `get_router_config` is the masked view for the frontend.
+pub async fn load_router_config(app: &tauri::AppHandle) -> Result<ClaudeCodeRouterConfig, String> {
+    let config = {
+        let conn = db::get_database_connection(app).map_err(|e| e.to_string())?;
+        load_router_config_from_db(&conn)?
+    };
+    
     // 如果数据库中没有数据，尝试从文件读取并同步到数据库
     if config.providers.is_empty() {
-        let config_path = get_router_config_path_with_custom(Some(&app)).await?;
+        let config_path = get_router_config_path_with_custom(Some(app)).await?;
         
         if config_path.exists() {
             let content = fs::read_to_string(&config_path)
                 .map_err(|e| format!("Failed to read config file: {}", e))?;
             
-            if let Ok(file_config) = serde_json::from_str::<ClaudeCodeRouterConfig>(&content) {
-                // 同步文件配置到数据库
-                update_router_config(app, file_config.clone()).await?;
-                return Ok(file_config);
+            if let Ok(file_config) = ClaudeCodeRouterConfig::from_json_str(&content) {
+                // 同步文件配置到数据库，文件内容以磁盘为准，不做路由校验
+                update_router_config(app.clone(), file_config, Some(true)).await?;
+                let conn = db::get_database_connection(app).map_err(|e| e.to_string())?;
+                return load_router_config_from_db(&conn);
             }
         }
     }
@@ -189,30 +171,160 @@ pub async fn get_router_config(app: tauri::AppHandle) -> Result<ClaudeCodeRouter
     Ok(config)
 }
 
-#[tauri::command] 
-pub async fn update_router_config(app: tauri::AppHandle, config: ClaudeCodeRouterConfig) -> Result<bool, String> {
-    println!("=== UPDATE_ROUTER_CONFIG CALLED ===");
-    println!("Saving {} providers to database", config.providers.len());
-    let config_path = get_router_config_path_with_custom(Some(&app)).await?;
-    
-    // 同时保存到数据库和文件
+/// A stored provider key as the frontend sees it.
+// `$VAR` 引用不是密钥本身，原样返回便于编辑
+pub fn mask_stored_key(stored: &str) -> Result<String, String> {
+    let plaintext = crypto::decrypt_secret(stored)?;
+    if plaintext.starts_with('$') {
+        Ok(plaintext)
+    } else {
+        Ok(crypto::mask_secret(&plaintext))
+    }
+}
+
+fn mask_secrets(config: &mut ClaudeCodeRouterConfig) -> Result<(), String> {
+    for provider in &mut config.providers {
+        provider.api_key = mask_stored_key(&provider.api_key)?;
+    }
+    if let Some(api_key) = &mut config.anthropic_api_key {
+        *api_key = mask_stored_key(api_key)?;
+    }
+    Ok(())
+}
+
+// 前端原样提交回来的脱敏值换回已保存的密钥；先按提供商名称匹配，改了名的再按脱敏值匹配
+fn restore_masked_secrets(stored: &ClaudeCodeRouterConfig, config: &mut ClaudeCodeRouterConfig) -> Result<(), String> {
+    for provider in &mut config.providers {
+        if provider.api_key.is_empty() || crypto::is_encrypted(&provider.api_key) {
+            continue;
+        }
+        let candidates = stored.providers.iter().filter(|p| p.name == provider.name)
+            .chain(stored.providers.iter().filter(|p| p.name != provider.name));
+        for previous in candidates {
+            if !previous.api_key.is_empty() && mask_stored_key(&previous.api_key)? == provider.api_key {
+                provider.api_key = previous.api_key.clone();
+                break;
+            }
+        }
+    }
+    if let (Some(api_key), Some(previous)) = (&mut config.anthropic_api_key, &stored.anthropic_api_key) {
+        if !crypto::is_encrypted(api_key) && mask_stored_key(previous)? == *api_key {
+            *api_key = previous.clone();
+        }
+    }
+    Ok(())
+}
+
+/// The router config for display: provider keys and `APIKEY` are masked. Saving the masked
+/// values back through `update_router_config` keeps the stored secrets.
+#[tauri::command]
+pub async fn get_router_config(app: tauri::AppHandle) -> Result<ClaudeCodeRouterConfig, String> {
+    println!("=== GET_ROUTER_CONFIG CALLED ===");
+    let mut config = load_router_config(&app).await?;
+    mask_secrets(&mut config)?;
+    Ok(config)
+}
+
+/// The plaintext key stored for the provider `name`; `reveal_api_key` for router providers.
+#[tauri::command]
+pub async fn reveal_router_provider_key(app: tauri::AppHandle, name: String) -> Result<String, String> {
     let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    let stored: Option<String> = conn.query_row(
+        "SELECT api_key FROM providers WHERE name = ?1",
+        [&name],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+
+    crypto::decrypt_secret(&stored.ok_or_else(|| format!("Provider '{}' not found", name))?)
+}
+
+/// The key written to `config.json` for `provider`: a referenced stored key is decrypted,
+/// otherwise the provider's own key is, which leaves `$VAR` references for CCR to expand.
+fn resolve_export_api_key(conn: &rusqlite::Connection, provider: &Provider) -> Result<String, String> {
+    let Some(key_id) = &provider.api_key_id else {
+        return crypto::decrypt_secret(&provider.api_key);
+    };
+
+    let stored: String = conn.query_row(
+        "SELECT ANTHROPIC_API_KEY FROM api_keys WHERE id = ?1",
+        [key_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Provider '{}' references an API key that no longer exists", provider.name))?;
+
+    crypto::decrypt_secret(&stored)
+}
+
+/// The key the app itself should use to call `provider`, with `$VAR` / `${VAR}` expanded.
+pub fn resolve_provider_api_key(conn: &Connection, provider: &Provider) -> Result<String, String> {
+    let key = resolve_export_api_key(conn, provider)?;
+
+    let Some(var) = key.strip_prefix('$') else {
+        return Ok(key);
+    };
+    let var = var.strip_prefix('{').and_then(|v| v.strip_suffix('}')).unwrap_or(var);
+    std::env::var(var).map_err(|_| format!("Environment variable '{}' used by provider '{}' is not set", var, provider.name))
+}
+
+/// Rewrites `config.json` if any provider references the stored key `key_id`, so that
+/// changing the key reaches every provider using it.
+pub async fn export_providers_using_key(app: &tauri::AppHandle, key_id: &str) -> Result<(), String> {
+    let config_path = get_router_config_path_with_custom(Some(app)).await?;
+    {
+        let mut conn = db::get_database_connection(app).map_err(|e| e.to_string())?;
+        let referenced: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM providers WHERE api_key_id = ?1",
+            [key_id],
+            |row| row.get(0),
+        ).map_err(|e| e.to_string())?;
+        if !referenced {
+            return Ok(());
+        }
+
+        let config = load_router_config_from_db(&conn)?;
+        save_router_config(&mut conn, &config_path, &config)?;
+    }
+    super::router_process::restart_after_save(app).await;
+
+    Ok(())
+}
+
+// 同步写入数据库和文件；数据库的修改在一个事务里，文件写入失败时一起回滚
+fn save_router_config(conn: &mut Connection, config_path: &std::path::Path, config: &ClaudeCodeRouterConfig) -> Result<(), String> {
+    save_router_config_with(conn, config_path, config, |_| Ok(()))
+}
+
+/// Like `save_router_config`, but also runs `in_transaction` in the same transaction before
+/// `config.json` is written, so its changes are only committed once the file is.
+pub fn save_router_config_with<F>(
+    conn: &mut Connection,
+    config_path: &std::path::Path,
+    config: &ClaudeCodeRouterConfig,
+    in_transaction: F,
+) -> Result<(), String>
+where
+    F: FnOnce(&Connection) -> Result<(), String>,
+{
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let conn = &*tx;
     let now = chrono::Utc::now().to_rfc3339();
     
     // 保存提供商到数据库
     // 先删除所有现有的提供商
     conn.execute("DELETE FROM providers", ()).map_err(|e| e.to_string())?;
     
-    // 插入新的提供商
-    for provider in &config.providers {
+    // 插入新的提供商，position 记录它们在配置中的顺序
+    for (position, provider) in config.providers.iter().enumerate() {
         let provider_id = uuid::Uuid::new_v4().to_string();
         let models_json = serde_json::to_string(&provider.models).unwrap_or_else(|_| "[]".to_string());
         let transformer_json = provider.transformer.as_ref()
             .map(|t| serde_json::to_string(t).unwrap_or_else(|_| "null".to_string()));
+        let extra_json = serde_json::to_string(&provider.extra).unwrap_or_else(|_| "{}".to_string());
+        let encrypted_api_key = crypto::encrypt_secret(&provider.api_key)?;
         
         conn.execute(
-            "INSERT INTO providers (id, name, api_base_url, api_key, models, transformer, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            (&provider_id, &provider.name, &provider.api_base_url, &provider.api_key, &models_json, &transformer_json, &now, &now),
+            "INSERT INTO providers (id, name, api_base_url, api_key, api_key_id, models, transformer, extra, position, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            (&provider_id, &provider.name, &provider.api_base_url, &encrypted_api_key, &provider.api_key_id, &models_json, &transformer_json, &extra_json, position as i64, &now, &now),
         ).map_err(|e| format!("Failed to save provider to database: {}", e))?;
     }
     
@@ -231,7 +343,7 @@ pub async fn update_router_config(app: tauri::AppHandle, config: ClaudeCodeRoute
         conn.execute("DELETE FROM router_configs WHERE config_key = 'anthropic_api_key'", ()).map_err(|e| e.to_string())?;
         conn.execute(
             "INSERT INTO router_configs (id, config_key, config_value, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (uuid::Uuid::new_v4().to_string(), "anthropic_api_key", api_key, now.clone(), now.clone()),
+            (uuid::Uuid::new_v4().to_string(), "anthropic_api_key", crypto::encrypt_secret(api_key)?, now.clone(), now.clone()),
         ).map_err(|e| format!("Failed to save anthropic_api_key to database: {}", e))?;
     } else {
         conn.execute("DELETE FROM router_configs WHERE config_key = 'anthropic_api_key'", ()).map_err(|e| e.to_string())?;
@@ -243,26 +355,118 @@ pub async fn update_router_config(app: tauri::AppHandle, config: ClaudeCodeRoute
             "INSERT INTO router_configs (id, config_key, config_value, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
             (uuid::Uuid::new_v4().to_string(), "proxy_url", proxy_url, now.clone(), now.clone()),
         ).map_err(|e| format!("Failed to save proxy_url to database: {}", e))?;
+    } else {
+        conn.execute("DELETE FROM router_configs WHERE config_key = 'proxy_url'", ()).map_err(|e| e.to_string())?;
     }
     
+    let globals = serde_json::Value::Object(globals_of(config)?);
+    conn.execute("DELETE FROM router_configs WHERE config_key = 'globals'", ()).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO router_configs (id, config_key, config_value, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        (uuid::Uuid::new_v4().to_string(), "globals", globals.to_string(), now.clone(), now.clone()),
+    ).map_err(|e| format!("Failed to save router globals to database: {}", e))?;
+    in_transaction(conn)?;
+    
     // 创建目录（如果不存在）
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create config directory: {}", e))?;
     }
     
-    let content = serde_json::to_string_pretty(&config)
+    // config.json is read by CCR itself, so secrets are only decrypted for the export
+    let mut export_config = config.clone();
+    for provider in &mut export_config.providers {
+        provider.api_key = resolve_export_api_key(conn, provider)?;
+        provider.api_key_id = None;
+    }
+    if let Some(api_key) = &mut export_config.anthropic_api_key {
+        *api_key = crypto::decrypt_secret(api_key)?;
+    }
+    
+    let content = serde_json::to_string_pretty(&export_config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
     
-    fs::write(&config_path, content)
+    fs::write(config_path, content)
         .map_err(|e| format!("Failed to write config file: {}", e))?;
     
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Providers、Router、APIKEY 和 PROXY_URL 单独存放，其余顶层字段都算 globals
+const SEPARATELY_STORED_KEYS: [&str; 4] = ["Providers", "Router", "APIKEY", "PROXY_URL"];
+
+fn globals_of(config: &ClaudeCodeRouterConfig) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut globals = match serde_json::to_value(config).map_err(|e| e.to_string())? {
+        serde_json::Value::Object(globals) => globals,
+        _ => serde_json::Map::new(),
+    };
+    for key in SEPARATELY_STORED_KEYS {
+        // remove 在 preserve_order 下会把最后一个字段换到被删的位置，打乱未知字段的顺序
+        globals.shift_remove(key);
+    }
+    Ok(globals)
+}
+
+/// Carries over top-level keys (`LOG`, `StatusLine`, ...) that were added, changed or removed by
+/// hand in `config.json` since the last save, unless `config` itself changes the same key.
+fn merge_file_edits(previous: &ClaudeCodeRouterConfig, config_path: &std::path::Path, config: &mut ClaudeCodeRouterConfig) -> Result<(), String> {
+    let Some(on_disk) = fs::read_to_string(config_path).ok()
+        .and_then(|content| ClaudeCodeRouterConfig::from_json_str(&content).ok()) else {
+        return Ok(());
+    };
+    let (previous, on_disk) = (globals_of(previous)?, globals_of(&on_disk)?);
+    let mut merged = match serde_json::to_value(&*config).map_err(|e| e.to_string())? {
+        serde_json::Value::Object(merged) => merged,
+        _ => return Ok(()),
+    };
+
+    // 三方比较：文件相对上次保存有变化，而这次编辑没有动这个字段时，以文件为准
+    let mut changed = false;
+    for (key, value) in &on_disk {
+        if previous.get(key) != Some(value) && merged.get(key) == previous.get(key) {
+            merged.insert(key.clone(), value.clone());
+            changed = true;
+        }
+    }
+    for (key, value) in &previous {
+        if !on_disk.contains_key(key) && merged.get(key) == Some(value) {
+            merged.shift_remove(key);
+            changed = true;
+        }
+    }
+
+    if changed {
+        *config = serde_json::from_value(serde_json::Value::Object(merged))
+            .map_err(|e| format!("Failed to merge edits from {}: {}", config_path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Saves the router config to the database and `config.json`. A config with any route pointing
+/// at an undeclared provider or model is refused unless `force` is set.
+#[tauri::command] 
+pub async fn update_router_config(app: tauri::AppHandle, mut config: ClaudeCodeRouterConfig, force: Option<bool>) -> Result<bool, String> {
+    println!("=== UPDATE_ROUTER_CONFIG CALLED ===");
+    println!("Saving {} providers to database", config.providers.len());
+    
+    let config_path = get_router_config_path_with_custom(Some(&app)).await?;
+    
+    {
+        let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        let previous = load_router_config_from_db(&conn)?;
+        restore_masked_secrets(&previous, &mut config)?;
+        merge_file_edits(&previous, &config_path, &mut config)?;
+        check_routes(&config, force)?;
+        save_router_config(&mut conn, &config_path, &config)?;
+    }
+    super::router_process::restart_after_save(&app).await;
+    
     Ok(true)
 }
 
 #[tauri::command]
-pub async fn create_router_config(app: tauri::AppHandle, config: ClaudeCodeRouterConfig) -> Result<ClaudeCodeRouterConfig, String> {
-    update_router_config(app, config.clone()).await?;
+pub async fn create_router_config(app: tauri::AppHandle, config: ClaudeCodeRouterConfig, force: Option<bool>) -> Result<ClaudeCodeRouterConfig, String> {
+    update_router_config(app, config.clone(), force).await?;
     Ok(config)
 }
 
@@ -277,25 +481,7 @@ pub async fn get_raw_router_config(app: tauri::AppHandle) -> Result<String, Stri
                 .map_err(|e| format!("Failed to create config directory: {}", e))?;
         }
         
-        let default_config = ClaudeCodeRouterConfig {
-            anthropic_api_key: None,
-            proxy_url: None,
-            log: None,
-            host: None,
-            non_interactive_mode: None,
-            api_timeout_ms: Some(600000),
-            custom_router_path: None,
-            providers: Vec::new(),
-            router: RouterConfig {
-                default: None,
-                background: None,
-                think: None,
-                long_context: None,
-                long_context_threshold: Some(60000),
-                web_search: None,
-            },
-            transformers: None,
-        };
+        let default_config = default_router_config();
         
         let content = serde_json::to_string_pretty(&default_config)
             .map_err(|e| format!("Failed to serialize default config: {}", e))?;
@@ -526,18 +712,24 @@ pub async fn select_router_config_path(app: tauri::AppHandle) -> Result<String,
             let path_str = std_path_buf.to_str().map_or_else(|| "invalid path".to_string(), |s| s.to_string());
             
             // 保存配置文件路径到数据库
-            let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
-            let now = chrono::Utc::now().to_rfc3339();
-            
-            // 删除现有的router配置路径
-            conn.execute("DELETE FROM current_router_config_path", ())
-                .map_err(|e| e.to_string())?;
+            {
+                let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+                let now = chrono::Utc::now().to_rfc3339();
+                
+                // 删除现有的router配置路径
+                conn.execute("DELETE FROM current_router_config_path", ())
+                    .map_err(|e| e.to_string())?;
+                
+                // 插入新的router配置路径
+                conn.execute(
+                    "INSERT INTO current_router_config_path (path, updated_at) VALUES (?1, ?2)",
+                    (&path_str, &now),
+                ).map_err(|e| format!("Failed to save router config path to database: {}", e))?;
+            }
             
-            // 插入新的router配置路径
-            conn.execute(
-                "INSERT INTO current_router_config_path (path, updated_at) VALUES (?1, ?2)",
-                (&path_str, &now),
-            ).map_err(|e| format!("Failed to save router config path to database: {}", e))?;
+            if let Err(e) = super::watcher::restart_config_watcher(&app).await {
+                eprintln!("Failed to restart config watcher: {}", e);
+            }
             
             Ok(path_str)
         }
@@ -551,13 +743,19 @@ pub async fn select_router_config_path(app: tauri::AppHandle) -> Result<String,
 }
 
 #[tauri::command]
-pub async fn save_raw_router_config(app: tauri::AppHandle, content: String) -> Result<bool, String> {
+pub async fn save_raw_router_config(app: tauri::AppHandle, content: String, force: Option<bool>) -> Result<bool, String> {
     let config_path = get_router_config_path_with_custom(Some(&app)).await?;
     
     // 验证JSON格式
     serde_json::from_str::<serde_json::Value>(&content)
         .map_err(|e| format!("无效的JSON格式: {}", e))?;
     
+    if !force.unwrap_or(false) {
+        let config = ClaudeCodeRouterConfig::from_json_str(&content)
+            .map_err(|e| format!("无效的配置格式: {}", e))?;
+        check_routes(&config, force)?;
+    }
+    
     // 创建目录（如果不存在）
     if let Some(parent) = config_path.parent() {
         fs::create_dir_all(parent)
@@ -567,5 +765,161 @@ pub async fn save_raw_router_config(app: tauri::AppHandle, content: String) -> R
     fs::write(&config_path, content)
         .map_err(|e| format!("Failed to save config file: {}", e))?;
     
+    super::router_process::restart_after_save(&app).await;
+    
     Ok(true)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_config() -> ClaudeCodeRouterConfig {
+        ClaudeCodeRouterConfig::from_json_str(&serde_json::json!({
+            "LOG": true,
+            "API_TIMEOUT_MS": 600000,
+            "zFutureOption": { "nested": [1, 2] },
+            "StatusLine": { "enabled": true },
+            "aFutureOption": "kept",
+            "Providers": [
+                { "name": "zeta", "api_base_url": "https://zeta.example.com/v1/chat/completions", "api_key": "sk-zeta", "models": ["z-1"], "max_retries": 3, "a_note": "x" },
+                { "name": "alpha", "api_base_url": "https://alpha.example.com/v1/chat/completions", "api_key": "sk-alpha", "models": ["a-1"] },
+                { "name": "mid", "api_base_url": "http://localhost:11434/v1/chat/completions", "api_key": "ollama", "models": ["m-1"] }
+            ],
+            "Router": { "default": "zeta,z-1", "longContextThreshold": 60000 }
+        }).to_string()).unwrap()
+    }
+
+    fn temp_config_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("claude-meta-router-{}", uuid::Uuid::new_v4())).join("config.json")
+    }
+
+    #[test]
+    fn round_trips_provider_order_and_unknown_keys() {
+        let mut conn = db::open_test_connection();
+        let path = temp_config_path();
+        let config = sample_config();
+
+        save_router_config(&mut conn, &path, &config).unwrap();
+        let mut loaded = load_router_config_from_db(&conn).unwrap();
+        for provider in &mut loaded.providers {
+            provider.api_key = crypto::decrypt_secret(&provider.api_key).unwrap();
+        }
+
+        let names: Vec<&str> = loaded.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["zeta", "alpha", "mid"]);
+        assert_eq!(loaded.extra.keys().collect::<Vec<_>>(), ["zFutureOption", "StatusLine", "aFutureOption"]);
+        assert_eq!(loaded.providers[0].extra.keys().collect::<Vec<_>>(), ["max_retries", "a_note"]);
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&config).unwrap());
+
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["zFutureOption"], serde_json::json!({ "nested": [1, 2] }));
+        assert_eq!(written["Providers"][0]["api_key"], "sk-zeta");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn masked_keys_from_the_frontend_keep_the_stored_secrets() {
+        let mut conn = db::open_test_connection();
+        let path = temp_config_path();
+        let mut config = sample_config();
+        config.anthropic_api_key = Some("router-secret-key".to_string());
+        config.providers[2].api_key = "$OLLAMA_KEY".to_string();
+        save_router_config(&mut conn, &path, &config).unwrap();
+
+        let stored = load_router_config_from_db(&conn).unwrap();
+        let mut shown = stored.clone();
+        mask_secrets(&mut shown).unwrap();
+        assert_eq!(shown.providers[0].api_key, crypto::mask_secret("sk-zeta"));
+        assert_eq!(shown.providers[2].api_key, "$OLLAMA_KEY");
+        assert_eq!(shown.anthropic_api_key.as_deref(), Some(crypto::mask_secret("router-secret-key").as_str()));
+
+        // 改名、改顺序并替换其中一个密钥后保存
+        shown.providers.swap(0, 1);
+        shown.providers[1].name = "zeta-renamed".to_string();
+        shown.providers[0].api_key = "sk-alpha-new".to_string();
+        restore_masked_secrets(&stored, &mut shown).unwrap();
+        save_router_config(&mut conn, &path, &shown).unwrap();
+
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["Providers"][0]["api_key"], "sk-alpha-new");
+        assert_eq!(written["Providers"][1]["api_key"], "sk-zeta");
+        assert_eq!(written["Providers"][2]["api_key"], "$OLLAMA_KEY");
+        assert_eq!(written["APIKEY"], "router-secret-key");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_router_api_key_is_encrypted_at_rest() {
+        let mut conn = db::open_test_connection();
+        let path = temp_config_path();
+        let mut config = sample_config();
+        config.anthropic_api_key = Some("router-secret-key".to_string());
+        save_router_config(&mut conn, &path, &config).unwrap();
+
+        let stored: String = conn.query_row(
+            "SELECT config_value FROM router_configs WHERE config_key = 'anthropic_api_key'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert!(crypto::is_encrypted(&stored));
+        assert_eq!(crypto::decrypt_secret(&stored).unwrap(), "router-secret-key");
+
+        // 旧版本存下的明文在迁移时加密
+        conn.execute("UPDATE router_configs SET config_value = 'legacy-plain' WHERE config_key = 'anthropic_api_key'", ()).unwrap();
+        crypto::encrypt_plaintext_rows(&conn).unwrap();
+        let migrated = load_router_config_from_db(&conn).unwrap().anthropic_api_key.unwrap();
+        assert!(crypto::is_encrypted(&migrated));
+        assert_eq!(crypto::decrypt_secret(&migrated).unwrap(), "legacy-plain");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keys_edited_by_hand_in_the_file_survive_the_next_save() {
+        let mut conn = db::open_test_connection();
+        let path = temp_config_path();
+        save_router_config(&mut conn, &path, &sample_config()).unwrap();
+        let previous = load_router_config_from_db(&conn).unwrap();
+
+        // 加载之后、保存之前手动改了文件
+        let mut on_disk: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let object = on_disk.as_object_mut().unwrap();
+        object.insert("StatusLine".to_string(), serde_json::json!({ "enabled": false }));
+        object.insert("LOG".to_string(), serde_json::json!(false));
+        object.insert("CUSTOM_ROUTER_PATH".to_string(), serde_json::json!("/tmp/router.js"));
+        object.shift_remove("aFutureOption");
+        fs::write(&path, serde_json::to_string_pretty(&on_disk).unwrap()).unwrap();
+
+        let mut edited = previous.clone();
+        edited.providers.truncate(2);
+        edited.extra.insert("zFutureOption".to_string(), serde_json::json!("edited in the app"));
+        merge_file_edits(&previous, &path, &mut edited).unwrap();
+        save_router_config(&mut conn, &path, &edited).unwrap();
+
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["StatusLine"], serde_json::json!({ "enabled": false }));
+        assert_eq!(written["CUSTOM_ROUTER_PATH"], "/tmp/router.js");
+        assert_eq!(written["LOG"], false);
+        assert_eq!(written["zFutureOption"], "edited in the app");
+        assert!(written.get("aFutureOption").is_none());
+        assert_eq!(written["Providers"].as_array().unwrap().len(), 2);
+        assert_eq!(load_router_config_from_db(&conn).unwrap().custom_router_path.as_deref(), Some("/tmp/router.js"));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_database_unchanged_when_the_file_cannot_be_written() {
+        let mut conn = db::open_test_connection();
+        let path = temp_config_path();
+        save_router_config(&mut conn, &path, &sample_config()).unwrap();
+
+        // 父路径是一个普通文件，创建目录会失败
+        let blocked = path.parent().unwrap().join("not-a-dir");
+        fs::write(&blocked, "").unwrap();
+        let mut edited = sample_config();
+        edited.providers.truncate(1);
+        assert!(save_router_config(&mut conn, &blocked.join("config.json"), &edited).is_err());
+
+        assert_eq!(load_router_config_from_db(&conn).unwrap().providers.len(), 3);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,348 @@
+// src-tauri/src/commands/router_logs.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::router::get_router_config_path_with_custom;
+
+const DEFAULT_TAIL_BYTES: u64 = 64 * 1024;
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterLogFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterLogEntry {
+    pub raw: String,
+    /// Normalized level name (`trace`, `debug`, `info`, `warn`, `error`, `fatal`), if known.
+    pub level: Option<String>,
+    pub time: Option<String>,
+    pub message: Option<String>,
+    /// The full record for JSON log lines.
+    pub fields: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterLogChunk {
+    pub file: String,
+    pub entries: Vec<RouterLogEntry>,
+    /// Pass back as `offset` to continue tailing after the last complete line.
+    pub next_offset: u64,
+    pub file_size: u64,
+    /// Set when the file shrank since `offset`, i.e. it was rotated or truncated.
+    pub reset: bool,
+}
+
+const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "fatal"];
+
+fn level_rank(level: &str) -> Option<usize> {
+    let level = level.to_ascii_lowercase();
+    let level = match level.as_str() {
+        "warning" => "warn",
+        "err" => "error",
+        other => other,
+    };
+    LEVELS.iter().position(|l| *l == level)
+}
+
+// CCR 使用 pino 输出 JSON 日志，level 是 10..60 的数字
+fn level_name(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Number(n) => {
+            let index = (n.as_u64()? / 10).checked_sub(1)? as usize;
+            LEVELS.get(index).map(|l| l.to_string())
+        }
+        serde_json::Value::String(s) => level_rank(s).map(|i| LEVELS[i].to_string()),
+        _ => None,
+    }
+}
+
+fn time_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Number(n) => chrono::DateTime::from_timestamp_millis(n.as_i64()?).map(|t| t.to_rfc3339()),
+        serde_json::Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn parse_log_line(line: &str) -> RouterLogEntry {
+    let fields = serde_json::from_str::<serde_json::Value>(line).ok().filter(|v| v.is_object());
+
+    let Some(record) = fields else {
+        // 非 JSON 行（例如进程直接输出的文本），尝试从前缀中识别级别
+        let level = line.split_whitespace()
+            .take(3)
+            .map(|word| word.trim_matches(|c: char| !c.is_ascii_alphabetic()))
+            .find_map(level_rank)
+            .map(|i| LEVELS[i].to_string());
+        return RouterLogEntry {
+            raw: line.to_string(),
+            level,
+            time: None,
+            message: Some(line.to_string()),
+            fields: None,
+        };
+    };
+
+    RouterLogEntry {
+        raw: line.to_string(),
+        level: record.get("level").and_then(level_name),
+        time: record.get("time").and_then(time_string),
+        message: record.get("msg").or_else(|| record.get("message")).and_then(|m| m.as_str()).map(|s| s.to_string()),
+        fields: Some(record),
+    }
+}
+
+fn matches_filter(entry: &RouterLogEntry, min_level: Option<usize>, contains: Option<&str>) -> bool {
+    let rank = entry.level.as_deref().and_then(level_rank);
+    if min_level.is_some_and(|min_level| rank.is_none_or(|rank| rank < min_level)) {
+        return false;
+    }
+    contains.is_none_or(|needle| entry.raw.to_lowercase().contains(&needle.to_lowercase()))
+}
+
+/// The directories CCR writes logs to: `logs/` next to the config and the config dir itself.
+async fn log_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let config_path = get_router_config_path_with_custom(Some(app)).await?;
+    let config_dir = config_path.parent()
+        .ok_or("Failed to resolve router config directory")?
+        .to_path_buf();
+    Ok(vec![config_dir.join("logs"), config_dir])
+}
+
+fn collect_log_files(dirs: &[PathBuf]) -> Vec<RouterLogFile> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("log") {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            files.push(RouterLogFile {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: path.to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified: metadata.modified().ok()
+                    .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+            });
+        }
+    }
+    // 最新的日志排在最前面
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+    files
+}
+
+/// Reads complete lines from `offset`, or the last `tail_bytes` of the file when no offset is given.
+fn read_log_chunk(path: &Path, offset: Option<u64>, tail_bytes: u64) -> Result<(Vec<String>, u64, u64, bool), String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open log file: {}", e))?;
+    let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let (start, reset, tailing) = match offset {
+        Some(offset) if offset > file_size => (0, true, false),
+        Some(offset) => (offset, false, false),
+        None => (file_size.saturating_sub(tail_bytes), false, true),
+    };
+    // 从文件中间开始读时，只有前一个字节不是换行符才说明第一行不完整
+    let skip_partial = tailing && start > 0 && !follows_newline(&mut file, start)?;
+
+    file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+    let mut buffer = Vec::new();
+    (&mut file).take(MAX_READ_BYTES).read_to_end(&mut buffer).map_err(|e| e.to_string())?;
+
+    // 只返回完整的行，未写完的最后一行留到下次读取
+    let mut complete_len = buffer.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let mut next_offset = start + complete_len as u64;
+    if complete_len == 0 && buffer.len() as u64 == MAX_READ_BYTES {
+        // 单行超过读取上限时截断返回，并跳过这一行剩下的部分，否则偏移量永远无法前进
+        complete_len = buffer.len();
+        next_offset = skip_past_newline(&mut file)?.unwrap_or(file_size);
+    }
+
+    let text = String::from_utf8_lossy(&buffer[..complete_len]);
+    let mut lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+    if skip_partial && !lines.is_empty() {
+        lines.remove(0);
+    }
+
+    Ok((lines, next_offset, file_size, reset))
+}
+
+fn follows_newline(file: &mut fs::File, position: u64) -> Result<bool, String> {
+    let mut previous = [0u8; 1];
+    file.seek(SeekFrom::Start(position - 1)).map_err(|e| e.to_string())?;
+    file.read_exact(&mut previous).map_err(|e| e.to_string())?;
+    Ok(previous[0] == b'\n')
+}
+
+/// Reads on from the current position and returns the offset just after the next newline.
+fn skip_past_newline(file: &mut fs::File) -> Result<Option<u64>, String> {
+    let mut position = file.stream_position().map_err(|e| e.to_string())?;
+    let mut block = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut block).map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if let Some(index) = block[..read].iter().position(|b| *b == b'\n') {
+            return Ok(Some(position + index as u64 + 1));
+        }
+        position += read as u64;
+    }
+}
+
+#[tauri::command]
+pub async fn list_router_log_files(app: AppHandle) -> Result<Vec<RouterLogFile>, String> {
+    Ok(collect_log_files(&log_dirs(&app).await?))
+}
+
+/// Tails a router log file. Without `file` the most recently modified log is used.
+#[tauri::command]
+pub async fn tail_router_log(
+    app: AppHandle,
+    file: Option<String>,
+    offset: Option<u64>,
+    tail_bytes: Option<u64>,
+    level: Option<String>,
+    contains: Option<String>,
+) -> Result<RouterLogChunk, String> {
+    let files = collect_log_files(&log_dirs(&app).await?);
+    // 只允许读取日志目录中列出的文件
+    let log_file = match &file {
+        Some(name) => files.iter().find(|f| &f.name == name)
+            .ok_or_else(|| format!("Log file '{}' not found", name))?,
+        None => files.first().ok_or("No router log files found")?,
+    };
+
+    let min_level = match level.as_deref().filter(|l| !l.is_empty()) {
+        Some(level) => Some(level_rank(level).ok_or_else(|| format!("Unknown log level '{}'", level))?),
+        None => None,
+    };
+    let contains = contains.as_deref().filter(|c| !c.is_empty());
+
+    let (lines, next_offset, file_size, reset) = read_log_chunk(
+        Path::new(&log_file.path),
+        offset,
+        tail_bytes.unwrap_or(DEFAULT_TAIL_BYTES).min(MAX_READ_BYTES),
+    )?;
+
+    let entries = lines.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_log_line(line))
+        .filter(|entry| matches_filter(entry, min_level, contains))
+        .collect();
+
+    Ok(RouterLogChunk {
+        file: log_file.name.clone(),
+        entries,
+        next_offset,
+        file_size,
+        reset,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const CCR_LOG: &str = include_str!("../../tests/fixtures/router/ccr.log");
+
+    fn temp_log(content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("claude-meta-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ccr.log");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn append(path: &Path, content: &[u8]) {
+        fs::OpenOptions::new().append(true).open(path).unwrap().write_all(content).unwrap();
+    }
+
+    fn cleanup(path: &Path) {
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn parses_pino_and_plain_text_lines() {
+        let entries: Vec<RouterLogEntry> = CCR_LOG.lines().map(parse_log_line).collect();
+        let levels: Vec<Option<&str>> = entries.iter().map(|e| e.level.as_deref()).collect();
+        assert_eq!(levels, [Some("info"), Some("debug"), Some("warn"), Some("error"), Some("warn")]);
+        assert_eq!(entries[0].message.as_deref(), Some("Server listening on 127.0.0.1:3456"));
+        assert_eq!(entries[0].time.as_deref(), Some("2025-07-14T08:00:00+00:00"));
+        assert!(entries[4].fields.is_none());
+
+        let warn = level_rank("warn");
+        let kept: Vec<&RouterLogEntry> = entries.iter().filter(|e| matches_filter(e, warn, Some("REQ-"))).collect();
+        assert_eq!(kept.len(), 2);
+    }
+
+    #[test]
+    fn tails_from_an_offset_and_holds_back_the_unfinished_line() {
+        let path = temp_log(CCR_LOG);
+        let (lines, offset, size, reset) = read_log_chunk(&path, Some(0), DEFAULT_TAIL_BYTES).unwrap();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(offset, size);
+        assert!(!reset);
+
+        append(&path, b"{\"level\":30,\"msg\":\"half");
+        let (lines, next, _, _) = read_log_chunk(&path, Some(offset), DEFAULT_TAIL_BYTES).unwrap();
+        assert!(lines.is_empty());
+        assert_eq!(next, offset);
+
+        append(&path, b" written\"}\n");
+        let (lines, _, _, _) = read_log_chunk(&path, Some(next), DEFAULT_TAIL_BYTES).unwrap();
+        assert_eq!(lines, [r#"{"level":30,"msg":"half written"}"#]);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn tail_keeps_the_first_line_when_it_starts_on_a_line_boundary() {
+        let path = temp_log(CCR_LOG);
+        let last_line = CCR_LOG.lines().last().unwrap();
+
+        let (lines, _, _, _) = read_log_chunk(&path, None, last_line.len() as u64 + 1).unwrap();
+        assert_eq!(lines, [last_line]);
+
+        // 从行中间开始时丢弃不完整的第一行
+        let (lines, _, _, _) = read_log_chunk(&path, None, last_line.len() as u64 - 5).unwrap();
+        assert!(lines.is_empty());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn skips_past_a_line_longer_than_the_read_limit() {
+        let long_line = "x".repeat(MAX_READ_BYTES as usize + 100);
+        let path = temp_log(&format!("{}\nafter\n", long_line));
+
+        let (lines, offset, _, _) = read_log_chunk(&path, Some(0), DEFAULT_TAIL_BYTES).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), MAX_READ_BYTES as usize);
+        assert_eq!(offset, long_line.len() as u64 + 1);
+
+        let (lines, _, _, _) = read_log_chunk(&path, Some(offset), DEFAULT_TAIL_BYTES).unwrap();
+        assert_eq!(lines, ["after"]);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn resets_when_the_file_was_truncated() {
+        let path = temp_log(CCR_LOG);
+        fs::write(&path, "restarted\n").unwrap();
+
+        let (lines, offset, _, reset) = read_log_chunk(&path, Some(CCR_LOG.len() as u64), DEFAULT_TAIL_BYTES).unwrap();
+        assert!(reset);
+        assert_eq!(lines, ["restarted"]);
+        assert_eq!(offset, 10);
+        cleanup(&path);
+    }
+}
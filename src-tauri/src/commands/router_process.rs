@@ -0,0 +1,502 @@
+// src-tauri/src/commands/router_process.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+use rusqlite::{Connection, OptionalExtension};
+use crate::commands::router::get_router_config_path_with_custom;
+use crate::db;
+
+pub const ROUTER_LOG_EVENT: &str = "router-process-log";
+pub const ROUTER_EXIT_EVENT: &str = "router-process-exited";
+
+const DEFAULT_ROUTER_BINARY: &str = "ccr";
+const LOG_BUFFER_LINES: usize = 2000;
+const LOG_FILE_NAME: &str = "router-process.log";
+const LOG_FILE_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const LOG_FILE_KEEP: usize = 3;
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Supervises the Claude Code Router process spawned by the app, held in Tauri managed state.
+#[derive(Default)]
+pub struct RouterProcess {
+    running: Mutex<Option<RunningRouter>>,
+    /// Recent lines for the UI; the full output goes to the rotating log file.
+    logs: Mutex<VecDeque<RouterLogLine>>,
+    log_file: Mutex<Option<RotatingLog>>,
+    last_exit_code: Mutex<Option<i32>>,
+}
+
+/// Appends lines to a log file, renaming it to `<name>.1` … `<name>.N` once it grows
+/// past `max_bytes`; the oldest file is dropped.
+struct RotatingLog {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file: Some(file), size, max_bytes, keep })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => self.file.insert(OpenOptions::new().create(true).append(true).open(&self.path)?),
+        };
+        writeln!(file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Windows 上不能重命名仍然打开的文件，先关闭
+        self.file = None;
+        for index in (1..self.keep).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+fn log_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app.path().app_log_dir().map_err(|e| e.to_string())?.join(LOG_FILE_NAME))
+}
+
+/// CCR always reads `$HOME/.claude-code-router/config.json`. Returns the home directory to
+/// start it with so that it reads `config_path`, or `None` when that is the default location.
+fn router_home(config_path: &Path) -> Result<Option<PathBuf>, String> {
+    let dir = config_path.parent()
+        .filter(|dir| config_path.file_name().is_some_and(|name| name == "config.json")
+            && dir.file_name().is_some_and(|name| name == ".claude-code-router"))
+        .ok_or_else(|| format!(
+            "Claude Code Router only reads .claude-code-router/config.json under its home directory; \
+             move {} to such a location to start the router from the app",
+            config_path.display()
+        ))?;
+    let home = dir.parent().ok_or("Invalid router config path")?;
+
+    if dirs::home_dir().as_deref() == Some(home) {
+        Ok(None)
+    } else {
+        Ok(Some(home.to_path_buf()))
+    }
+}
+
+struct RunningRouter {
+    pid: u32,
+    binary: String,
+    started_at: String,
+    /// Taken by the first `stop`; a later one only waits for the exit.
+    kill_tx: Option<oneshot::Sender<()>>,
+    /// Taken while a `stop` waits and put back if the process outlives the timeout.
+    exited_rx: Option<oneshot::Receiver<()>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterLogLine {
+    /// `stdout` or `stderr`.
+    pub stream: String,
+    pub line: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterProcessStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub binary: String,
+    pub started_at: Option<String>,
+    pub last_exit_code: Option<i32>,
+    pub restart_on_save: bool,
+    /// Rotating file that receives every output line.
+    pub log_file: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterProcessOptions {
+    pub binary: String,
+    pub restart_on_save: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct RouterExitEvent {
+    pid: u32,
+    exit_code: Option<i32>,
+}
+
+// 进程相关的设置和路由配置一起存放在 router_configs 表中
+fn read_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT config_value FROM router_configs WHERE config_key = ?1",
+        [key],
+        |row| row.get::<_, Option<String>>(0),
+    ).optional().map(|v| v.flatten()).map_err(|e| e.to_string())
+}
+
+fn write_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO router_configs (id, config_key, config_value, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(config_key) DO UPDATE SET config_value = excluded.config_value, updated_at = excluded.updated_at",
+        (uuid::Uuid::new_v4().to_string(), key, value, &now),
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn load_options(conn: &Connection) -> Result<RouterProcessOptions, String> {
+    Ok(RouterProcessOptions {
+        binary: read_setting(conn, "process_binary")?
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ROUTER_BINARY.to_string()),
+        restart_on_save: read_setting(conn, "process_restart_on_save")?.as_deref() == Some("true"),
+    })
+}
+
+fn push_log(app: &AppHandle, stream: &str, line: String) {
+    let entry = RouterLogLine {
+        stream: stream.to_string(),
+        line,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+
+    {
+        let state = app.state::<RouterProcess>();
+        let mut log_file = state.log_file.lock().unwrap_or_else(|e| e.into_inner());
+        if log_file.is_none() {
+            match log_file_path(app).and_then(|path| RotatingLog::open(path, LOG_FILE_MAX_BYTES, LOG_FILE_KEEP).map_err(|e| e.to_string())) {
+                Ok(opened) => *log_file = Some(opened),
+                Err(e) => eprintln!("Failed to open router log file: {}", e),
+            }
+        }
+        if let Some(log_file) = log_file.as_mut() {
+            if let Err(e) = log_file.write_line(&format!("{} [{}] {}", entry.timestamp, entry.stream, entry.line)) {
+                eprintln!("Failed to write router log file: {}", e);
+            }
+        }
+    }
+
+    {
+        let state = app.state::<RouterProcess>();
+        let mut logs = state.logs.lock().unwrap_or_else(|e| e.into_inner());
+        if logs.len() == LOG_BUFFER_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(entry.clone());
+    }
+
+    if let Err(e) = app.emit(ROUTER_LOG_EVENT, entry) {
+        eprintln!("Failed to emit {}: {}", ROUTER_LOG_EVENT, e);
+    }
+}
+
+fn forward_output<R: AsyncRead + Unpin + Send + 'static>(app: AppHandle, stream: &'static str, reader: R) {
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            push_log(&app, stream, line);
+        }
+    });
+}
+
+fn current_options(app: &AppHandle) -> Result<RouterProcessOptions, String> {
+    let conn = db::get_database_connection(app).map_err(|e| e.to_string())?;
+    load_options(&conn)
+}
+
+fn status(app: &AppHandle) -> Result<RouterProcessStatus, String> {
+    let options = current_options(app)?;
+    let state = app.state::<RouterProcess>();
+    let running = state.running.lock().map_err(|e| e.to_string())?;
+    let last_exit_code = *state.last_exit_code.lock().map_err(|e| e.to_string())?;
+
+    Ok(RouterProcessStatus {
+        running: running.is_some(),
+        pid: running.as_ref().map(|r| r.pid),
+        binary: running.as_ref().map_or(options.binary, |r| r.binary.clone()),
+        started_at: running.as_ref().map(|r| r.started_at.clone()),
+        last_exit_code,
+        restart_on_save: options.restart_on_save,
+        log_file: log_file_path(app).ok().map(|p| p.to_string_lossy().to_string()),
+    })
+}
+
+async fn start(app: &AppHandle) -> Result<RouterProcessStatus, String> {
+    let options = current_options(app)?;
+    let home = router_home(&get_router_config_path_with_custom(Some(app)).await?)?;
+
+    let state = app.state::<RouterProcess>();
+    let mut running = state.running.lock().map_err(|e| e.to_string())?;
+    if running.is_some() {
+        return Err("Claude Code Router is already running".to_string());
+    }
+
+    let mut command = Command::new(&options.binary);
+    // 配置文件不在默认位置时，通过 HOME 让 CCR 读取本应用管理的那份配置
+    if let Some(home) = &home {
+        command.env("HOME", home).env("USERPROFILE", home);
+    }
+    let mut child = command
+        .arg("start")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start '{}': {}", options.binary, e))?;
+    let pid = child.id().ok_or("Router process exited immediately")?;
+
+    if let Some(stdout) = child.stdout.take() {
+        forward_output(app.clone(), "stdout", stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_output(app.clone(), "stderr", stderr);
+    }
+
+    let (kill_tx, kill_rx) = oneshot::channel();
+    let (exited_tx, exited_rx) = oneshot::channel();
+    // 先登记再启动监督任务，避免进程立即退出时监督任务找不到自己的记录
+    *running = Some(RunningRouter {
+        pid,
+        binary: options.binary,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        kill_tx: Some(kill_tx),
+        exited_rx: Some(exited_rx),
+    });
+    drop(running);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let exit_status = tokio::select! {
+            status = child.wait() => status,
+            _ = kill_rx => {
+                let _ = child.kill().await;
+                child.wait().await
+            }
+        };
+        let exit_code = exit_status.ok().and_then(|s| s.code());
+
+        let state = handle.state::<RouterProcess>();
+        {
+            let mut running = state.running.lock().unwrap_or_else(|e| e.into_inner());
+            // 重启时新进程可能已经登记，只清理属于自己的记录
+            if running.as_ref().is_some_and(|r| r.pid == pid) {
+                *running = None;
+            }
+        }
+        *state.last_exit_code.lock().unwrap_or_else(|e| e.into_inner()) = exit_code;
+
+        if let Err(e) = handle.emit(ROUTER_EXIT_EVENT, RouterExitEvent { pid, exit_code }) {
+            eprintln!("Failed to emit {}: {}", ROUTER_EXIT_EVENT, e);
+        }
+        let _ = exited_tx.send(());
+    });
+
+    status(app)
+}
+
+/// Kills the registered process and waits for it to exit. The entry stays registered until
+/// the supervising task sees the exit, so a process that outlives `timeout` is still tracked.
+async fn stop_running(running: &Mutex<Option<RunningRouter>>, timeout: Duration) -> Result<(), String> {
+    let (pid, kill_tx, exited_rx) = {
+        let mut running = running.lock().map_err(|e| e.to_string())?;
+        let Some(running) = running.as_mut() else {
+            return Ok(());
+        };
+        (running.pid, running.kill_tx.take(), running.exited_rx.take())
+    };
+
+    if let Some(kill_tx) = kill_tx {
+        let _ = kill_tx.send(());
+    }
+    let Some(mut exited_rx) = exited_rx else {
+        return Err(format!("Router process {} is already being stopped", pid));
+    };
+
+    if tokio::time::timeout(timeout, &mut exited_rx).await.is_err() {
+        // 进程还没退出：放回等待句柄，之后再次停止时继续等它
+        let mut running = running.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(running) = running.as_mut().filter(|r| r.pid == pid) {
+            running.exited_rx = Some(exited_rx);
+        }
+        return Err(format!("Router process {} did not exit in time", pid));
+    }
+
+    Ok(())
+}
+
+async fn stop(app: &AppHandle) -> Result<RouterProcessStatus, String> {
+    stop_running(&app.state::<RouterProcess>().running, STOP_TIMEOUT).await?;
+    status(app)
+}
+
+fn is_running(app: &AppHandle) -> bool {
+    app.state::<RouterProcess>().running.lock().is_ok_and(|r| r.is_some())
+}
+
+/// Restarts the router after its config was saved, if it is running and the user opted in.
+pub async fn restart_after_save(app: &AppHandle) {
+    let restart_on_save = current_options(app).map(|o| o.restart_on_save).unwrap_or(false);
+    if !restart_on_save || !is_running(app) {
+        return;
+    }
+    if let Err(e) = restart_router_process(app.clone()).await {
+        eprintln!("Failed to restart Claude Code Router after saving config: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn start_router_process(app: AppHandle) -> Result<RouterProcessStatus, String> {
+    start(&app).await
+}
+
+#[tauri::command]
+pub async fn stop_router_process(app: AppHandle) -> Result<RouterProcessStatus, String> {
+    stop(&app).await
+}
+
+#[tauri::command]
+pub async fn restart_router_process(app: AppHandle) -> Result<RouterProcessStatus, String> {
+    stop(&app).await?;
+    start(&app).await
+}
+
+#[tauri::command]
+pub async fn get_router_process_status(app: AppHandle) -> Result<RouterProcessStatus, String> {
+    status(&app)
+}
+
+/// Returns the most recent captured output lines, oldest first.
+#[tauri::command]
+pub async fn get_router_process_logs(app: AppHandle, limit: Option<usize>) -> Result<Vec<RouterLogLine>, String> {
+    let state = app.state::<RouterProcess>();
+    let logs = state.logs.lock().map_err(|e| e.to_string())?;
+    let skip = limit.map_or(0, |limit| logs.len().saturating_sub(limit));
+    Ok(logs.iter().skip(skip).cloned().collect())
+}
+
+#[tauri::command]
+pub async fn get_router_process_options(app: AppHandle) -> Result<RouterProcessOptions, String> {
+    current_options(&app)
+}
+
+#[tauri::command]
+pub async fn set_router_process_options(app: AppHandle, options: RouterProcessOptions) -> Result<RouterProcessOptions, String> {
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    write_setting(&conn, "process_binary", options.binary.trim())?;
+    write_setting(&conn, "process_restart_on_save", if options.restart_on_save { "true" } else { "false" })?;
+    load_options(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("claude-meta-router-log-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn rotates_the_log_file_and_keeps_a_fixed_number_of_files() {
+        let dir = temp_dir();
+        let path = dir.join(LOG_FILE_NAME);
+        let mut log = RotatingLog::open(path.clone(), 20, 2).unwrap();
+
+        for line in ["line-1 aaaaa", "line-2 bbbbb", "line-3 ccccc", "line-4 ddddd"] {
+            log.write_line(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line-4 ddddd\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "line-3 ccccc\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "line-2 bbbbb\n");
+        assert!(!rotated_path(&path, 3).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn appends_to_an_existing_log_file() {
+        let dir = temp_dir();
+        let path = dir.join(LOG_FILE_NAME);
+        RotatingLog::open(path.clone(), 1024, 2).unwrap().write_line("first").unwrap();
+        RotatingLog::open(path.clone(), 1024, 2).unwrap().write_line("second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_process_that_outlives_the_stop_timeout_stays_registered() {
+        let (kill_tx, mut kill_rx) = oneshot::channel();
+        let (exited_tx, exited_rx) = oneshot::channel();
+        let running = Mutex::new(Some(RunningRouter {
+            pid: 42,
+            binary: DEFAULT_ROUTER_BINARY.to_string(),
+            started_at: String::new(),
+            kill_tx: Some(kill_tx),
+            exited_rx: Some(exited_rx),
+        }));
+
+        let error = stop_running(&running, Duration::from_millis(10)).await.unwrap_err();
+        assert!(error.contains("did not exit in time"), "{}", error);
+        assert!(kill_rx.try_recv().is_ok());
+        {
+            let entry = running.lock().unwrap();
+            let entry = entry.as_ref().expect("the entry is kept while the process runs");
+            assert_eq!(entry.pid, 42);
+            assert!(entry.exited_rx.is_some());
+        }
+
+        // 监督任务看到退出后清理记录，再次停止只需等待
+        exited_tx.send(()).unwrap();
+        stop_running(&running, Duration::from_millis(10)).await.unwrap();
+        *running.lock().unwrap() = None;
+        stop_running(&running, Duration::from_millis(10)).await.unwrap();
+    }
+
+    #[test]
+    fn starts_the_router_with_the_managed_config_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(router_home(&home.join(".claude-code-router").join("config.json")).unwrap(), None);
+
+        let custom = PathBuf::from("/srv/profiles/work");
+        assert_eq!(
+            router_home(&custom.join(".claude-code-router").join("config.json")).unwrap(),
+            Some(custom)
+        );
+
+        assert!(router_home(Path::new("/srv/router/custom.json")).is_err());
+        assert!(router_home(Path::new("/srv/router/config.json")).is_err());
+    }
+}
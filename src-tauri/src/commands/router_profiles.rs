@@ -0,0 +1,288 @@
+// src-tauri/src/commands/router_profiles.rs
+
+use std::path::Path;
+use tauri::AppHandle;
+use rusqlite::{Connection, OptionalExtension};
+use crate::db;
+use crate::crypto;
+use crate::commands::router::{backup_router_config, get_router_config_path_with_custom, load_router_config, save_router_config_with};
+use crate::commands::router_validation::check_routes;
+use crate::models::{ClaudeCodeRouterConfig, RouterProfile, CreateRouterProfileRequest, UpdateRouterProfileRequest};
+
+const PROFILE_COLUMNS: &str = "id, name, description, config, is_active, created_at, updated_at";
+
+fn row_to_profile(row: &rusqlite::Row) -> rusqlite::Result<RouterProfile> {
+    let config_json: String = row.get(3)?;
+    let config = ClaudeCodeRouterConfig::from_json_str(&config_json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?;
+
+    Ok(RouterProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        config,
+        is_active: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+// 与 providers 表一致，配置里的密钥只以密文形式落库；返回给前端的也是同一份密文形式，
+// 这样 create/update 与 list/get 的结果一致
+fn encrypt_config(mut config: ClaudeCodeRouterConfig) -> Result<ClaudeCodeRouterConfig, String> {
+    for provider in &mut config.providers {
+        provider.api_key = crypto::encrypt_secret(&provider.api_key)?;
+    }
+    if let Some(api_key) = &mut config.anthropic_api_key {
+        *api_key = crypto::encrypt_secret(api_key)?;
+    }
+    Ok(config)
+}
+
+fn serialize_config(config: &ClaudeCodeRouterConfig) -> Result<String, String> {
+    serde_json::to_string(config).map_err(|e| e.to_string())
+}
+
+fn load_profile(conn: &Connection, id: &str) -> Result<RouterProfile, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM router_profiles WHERE id = ?1", PROFILE_COLUMNS),
+        [id],
+        row_to_profile,
+    ).optional().map_err(|e| e.to_string())?
+    .ok_or_else(|| "Router profile not found".to_string())
+}
+
+#[tauri::command]
+pub async fn get_router_profiles(app: AppHandle) -> Result<Vec<RouterProfile>, String> {
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM router_profiles ORDER BY is_active DESC, name ASC", PROFILE_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let profiles = stmt.query_map([], row_to_profile).map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for profile in profiles {
+        result.push(profile.map_err(|e| e.to_string())?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn create_router_profile(app: AppHandle, request: CreateRouterProfileRequest) -> Result<RouterProfile, String> {
+    let config = match request.config {
+        Some(config) => config,
+        None => load_router_config(&app).await?,
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let profile = RouterProfile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name,
+        description: request.description,
+        config: encrypt_config(config)?,
+        is_active: false,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO router_profiles (id, name, description, config, is_active, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        (&profile.id, &profile.name, &profile.description, serialize_config(&profile.config)?, &profile.is_active, &profile.created_at, &profile.updated_at),
+    ).map_err(|e| format!("Failed to create router profile: {}", e))?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn update_router_profile(app: AppHandle, id: String, request: UpdateRouterProfileRequest) -> Result<RouterProfile, String> {
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+    let mut profile = load_profile(&conn, &id)?;
+
+    if let Some(name) = request.name {
+        profile.name = name;
+    }
+    if let Some(description) = request.description {
+        profile.description = Some(description);
+    }
+    if let Some(config) = request.config {
+        profile.config = encrypt_config(config)?;
+    }
+    profile.updated_at = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        "UPDATE router_profiles SET name = ?1, description = ?2, config = ?3, updated_at = ?4 WHERE id = ?5",
+        (&profile.name, &profile.description, serialize_config(&profile.config)?, &profile.updated_at, &id),
+    ).map_err(|e| format!("Failed to update router profile: {}", e))?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn delete_router_profile(app: AppHandle, id: String) -> Result<bool, String> {
+    let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+
+    let affected_rows = conn.execute(
+        "DELETE FROM router_profiles WHERE id = ?1",
+        [&id],
+    ).map_err(|e| e.to_string())?;
+
+    Ok(affected_rows > 0)
+}
+
+// 配置和激活标记在同一个事务里写入，config.json 写入失败时两者都不变
+fn activate_profile(conn: &mut Connection, config_path: &Path, profile: &mut RouterProfile, force: Option<bool>) -> Result<(), String> {
+    check_routes(&profile.config, force)?;
+
+    let updated_at = chrono::Utc::now().to_rfc3339();
+    save_router_config_with(conn, config_path, &profile.config, |tx| {
+        tx.execute(
+            "UPDATE router_profiles SET is_active = (id = ?1), updated_at = CASE WHEN id = ?1 THEN ?2 ELSE updated_at END",
+            (&profile.id, &updated_at),
+        ).map(|_| ()).map_err(|e| e.to_string())
+    })?;
+
+    profile.is_active = true;
+    profile.updated_at = updated_at;
+    Ok(())
+}
+
+/// Backs up the current `config.json`, then makes the profile the live router config.
+#[tauri::command]
+pub async fn activate_router_profile(app: AppHandle, id: String, force: Option<bool>) -> Result<RouterProfile, String> {
+    let config_path = get_router_config_path_with_custom(Some(&app)).await?;
+    if config_path.exists() {
+        backup_router_config(app.clone()).await?;
+    }
+
+    let profile = {
+        let mut conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        let mut profile = load_profile(&conn, &id)?;
+        activate_profile(&mut conn, &config_path, &mut profile, force)?;
+        profile
+    };
+    super::router_process::restart_after_save(&app).await;
+
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(conn: &Connection, config: ClaudeCodeRouterConfig) -> RouterProfile {
+        let now = chrono::Utc::now().to_rfc3339();
+        let id = uuid::Uuid::new_v4().to_string();
+        let profile = RouterProfile {
+            name: format!("work-{}", id),
+            id,
+            description: None,
+            config: encrypt_config(config).unwrap(),
+            is_active: false,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        conn.execute(
+            "INSERT INTO router_profiles (id, name, description, config, is_active, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (&profile.id, &profile.name, &profile.description, serialize_config(&profile.config).unwrap(), &profile.is_active, &profile.created_at, &profile.updated_at),
+        ).unwrap();
+        profile
+    }
+
+    #[test]
+    fn created_and_loaded_profiles_have_the_same_shape() {
+        let conn = db::open_test_connection();
+        let config = ClaudeCodeRouterConfig::from_json_str(r#"{
+            "Providers": [{ "name": "deepseek", "api_base_url": "https://api.deepseek.com/chat/completions", "api_key": "sk-plain", "models": ["deepseek-chat"] }]
+        }"#).unwrap();
+
+        let created = insert(&conn, config);
+        let loaded = load_profile(&conn, &created.id).unwrap();
+
+        assert!(crypto::is_encrypted(&created.config.providers[0].api_key));
+        assert_eq!(created.config.providers[0].api_key, loaded.config.providers[0].api_key);
+        assert_eq!(crypto::decrypt_secret(&loaded.config.providers[0].api_key).unwrap(), "sk-plain");
+    }
+
+    #[test]
+    fn encrypting_a_stored_config_again_leaves_it_unchanged() {
+        crypto::init_test_master_key();
+        let config = ClaudeCodeRouterConfig::from_json_str(r#"{
+            "Providers": [
+                { "name": "deepseek", "api_base_url": "https://api.deepseek.com/chat/completions", "api_key": "sk-plain", "models": ["deepseek-chat"] },
+                { "name": "ollama", "api_base_url": "http://localhost:11434/v1/chat/completions", "api_key": "", "models": ["qwen2.5-coder:latest"] }
+            ]
+        }"#).unwrap();
+
+        let once = encrypt_config(config).unwrap();
+        let twice = encrypt_config(once.clone()).unwrap();
+        assert_eq!(once.providers[1].api_key, "");
+        assert_eq!(serialize_config(&once).unwrap(), serialize_config(&twice).unwrap());
+    }
+
+    fn active_ids(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare("SELECT id FROM router_profiles WHERE is_active = 1").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    fn profile_config(provider: &str) -> ClaudeCodeRouterConfig {
+        ClaudeCodeRouterConfig::from_json_str(&serde_json::json!({
+            "Providers": [{ "name": provider, "api_base_url": "https://api.example.com/v1/chat/completions", "api_key": "sk-plain", "models": ["m-1"] }],
+            "Router": { "default": format!("{},m-1", provider) }
+        }).to_string()).unwrap()
+    }
+
+    #[test]
+    fn activation_writes_the_config_and_moves_the_active_flag() {
+        let mut conn = db::open_test_connection();
+        let dir = std::env::temp_dir().join(format!("claude-meta-profiles-{}", uuid::Uuid::new_v4()));
+        let mut first = insert(&conn, profile_config("first"));
+        let mut second = insert(&conn, profile_config("second"));
+
+        activate_profile(&mut conn, &dir.join("config.json"), &mut first, None).unwrap();
+        activate_profile(&mut conn, &dir.join("config.json"), &mut second, None).unwrap();
+
+        assert!(second.is_active);
+        assert_eq!(active_ids(&conn), [second.id.clone()]);
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("config.json")).unwrap()).unwrap();
+        assert_eq!(written["Providers"][0]["name"], "second");
+        assert_eq!(written["Providers"][0]["api_key"], "sk-plain");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_write_leaves_the_active_profile_unchanged() {
+        let mut conn = db::open_test_connection();
+        let dir = std::env::temp_dir().join(format!("claude-meta-profiles-{}", uuid::Uuid::new_v4()));
+        let mut first = insert(&conn, profile_config("first"));
+        let mut second = insert(&conn, profile_config("second"));
+        activate_profile(&mut conn, &dir.join("config.json"), &mut first, None).unwrap();
+
+        // 父路径是一个普通文件，config.json 无法写入
+        let blocked = dir.join("not-a-dir");
+        std::fs::write(&blocked, "").unwrap();
+        assert!(activate_profile(&mut conn, &blocked.join("config.json"), &mut second, None).is_err());
+
+        assert!(!second.is_active);
+        assert_eq!(active_ids(&conn), [first.id.clone()]);
+        let providers: String = conn.query_row("SELECT group_concat(name) FROM providers", [], |row| row.get(0)).unwrap();
+        assert_eq!(providers, "first");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_routes_block_activation_unless_forced() {
+        let mut conn = db::open_test_connection();
+        let dir = std::env::temp_dir().join(format!("claude-meta-profiles-{}", uuid::Uuid::new_v4()));
+        let mut config = profile_config("first");
+        config.router.default = Some("missing,m-1".to_string());
+        let mut profile = insert(&conn, config);
+
+        assert!(activate_profile(&mut conn, &dir.join("config.json"), &mut profile, None).is_err());
+        assert!(active_ids(&conn).is_empty());
+        activate_profile(&mut conn, &dir.join("config.json"), &mut profile, Some(true)).unwrap();
+        assert_eq!(active_ids(&conn), [profile.id.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
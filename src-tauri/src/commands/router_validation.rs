@@ -0,0 +1,144 @@
+// src-tauri/src/commands/router_validation.rs
+
+use serde::{Deserialize, Serialize};
+use crate::models::ClaudeCodeRouterConfig;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouteValidationError {
+    /// The config key, e.g. `Router.default`.
+    pub field: String,
+    pub value: String,
+    pub message: String,
+}
+
+/// Provider names and their declared models, in the order they appear in the config.
+type DeclaredProviders = Vec<(String, Vec<String>)>;
+
+fn validate_route(field: &str, value: &str, providers: &DeclaredProviders) -> Option<RouteValidationError> {
+    let error = |message: String| Some(RouteValidationError {
+        field: field.to_string(),
+        value: value.to_string(),
+        message,
+    });
+
+    let Some((provider_name, model)) = value.split_once(',') else {
+        return error("Route must be in the form \"provider,model\"".to_string());
+    };
+    let (provider_name, model) = (provider_name.trim(), model.trim());
+    if provider_name.is_empty() || model.is_empty() {
+        return error("Route must name both a provider and a model".to_string());
+    }
+
+    let Some((_, models)) = providers.iter().find(|(name, _)| name == provider_name) else {
+        return error(format!("Provider '{}' is not declared in Providers", provider_name));
+    };
+    if !models.iter().any(|m| m == model) {
+        return error(format!("Model '{}' is not declared for provider '{}'", model, provider_name));
+    }
+
+    None
+}
+
+fn validate_routes(providers: &DeclaredProviders, routes: &[(&str, Option<&str>)]) -> Vec<RouteValidationError> {
+    routes.iter()
+        .filter_map(|(field, value)| value.filter(|v| !v.trim().is_empty()).map(|v| (field, v)))
+        .filter_map(|(field, value)| validate_route(field, value, providers))
+        .collect()
+}
+
+/// Checks every route of `config` against the providers and models it declares.
+pub fn collect_route_errors(config: &ClaudeCodeRouterConfig) -> Vec<RouteValidationError> {
+    let providers = config.providers.iter()
+        .map(|p| (p.name.clone(), p.models.clone()))
+        .collect();
+    let router = &config.router;
+
+    validate_routes(&providers, &[
+        ("Router.default", router.default.as_deref()),
+        ("Router.background", router.background.as_deref()),
+        ("Router.think", router.think.as_deref()),
+        ("Router.longContext", router.long_context.as_deref()),
+        ("Router.webSearch", router.web_search.as_deref()),
+    ])
+}
+
+/// Formats validation errors into the message returned when a save is refused.
+pub fn describe_route_errors(errors: &[RouteValidationError]) -> String {
+    let details: Vec<String> = errors.iter()
+        .map(|e| format!("{} = \"{}\": {}", e.field, e.value, e.message))
+        .collect();
+    format!("Invalid router config (save with force to override): {}", details.join("; "))
+}
+
+/// Refuses a config with invalid routes unless `force` is set.
+pub fn check_routes(config: &ClaudeCodeRouterConfig, force: Option<bool>) -> Result<(), String> {
+    if force.unwrap_or(false) {
+        return Ok(());
+    }
+    let errors = collect_route_errors(config);
+    if !errors.is_empty() {
+        return Err(describe_route_errors(&errors));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn validate_router_config(config: ClaudeCodeRouterConfig) -> Vec<RouteValidationError> {
+    collect_route_errors(&config)
+}
+
+#[tauri::command]
+pub fn validate_raw_router_config(content: String) -> Result<Vec<RouteValidationError>, String> {
+    let config = ClaudeCodeRouterConfig::from_json_str(&content)
+        .map_err(|e| format!("无效的配置格式: {}", e))?;
+    Ok(collect_route_errors(&config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(router: serde_json::Value) -> ClaudeCodeRouterConfig {
+        ClaudeCodeRouterConfig::from_json_str(&serde_json::json!({
+            "Providers": [
+                { "name": "openrouter", "api_base_url": "https://openrouter.ai/api/v1/chat/completions", "models": ["anthropic/claude-sonnet-4", "google/gemini-2.5-pro"] },
+                { "name": "ollama", "api_base_url": "http://localhost:11434/v1/chat/completions", "models": ["qwen2.5-coder:latest"] }
+            ],
+            "Router": router,
+        }).to_string()).unwrap()
+    }
+
+    #[test]
+    fn accepts_declared_routes_and_skips_empty_ones() {
+        let errors = collect_route_errors(&config(serde_json::json!({
+            "default": "openrouter,anthropic/claude-sonnet-4",
+            "background": " ollama , qwen2.5-coder:latest ",
+            "think": "",
+        })));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn reports_each_kind_of_bad_route() {
+        let errors = collect_route_errors(&config(serde_json::json!({
+            "default": "openrouter",
+            "background": "ollama,",
+            "think": "deepseek,deepseek-reasoner",
+            "longContext": "openrouter,gpt-4o",
+        })));
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["Router.default", "Router.background", "Router.think", "Router.longContext"]);
+        assert!(errors[0].message.contains("provider,model"));
+        assert!(errors[1].message.contains("both a provider and a model"));
+        assert!(errors[2].message.contains("Provider 'deepseek'"));
+        assert!(errors[3].message.contains("Model 'gpt-4o'"));
+    }
+
+    #[test]
+    fn describes_errors_with_field_and_value() {
+        let errors = collect_route_errors(&config(serde_json::json!({ "default": "openrouter" })));
+        let message = describe_route_errors(&errors);
+        assert!(message.contains("Router.default = \"openrouter\""), "{}", message);
+        assert!(message.contains("force"));
+    }
+}
@@ -0,0 +1,268 @@
+// src-tauri/src/commands/settings_layers.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::config::expand_home_path;
+use crate::commands::permissions::read_settings;
+use crate::commands::project_db::get_project_by_id_internal;
+use crate::db;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettingsLayer {
+    /// `user`, `project`, `local` or `enterprise`.
+    pub scope: String,
+    pub path: String,
+    pub exists: bool,
+    /// Set when the file exists but could not be read; the layer is then left out of the merge.
+    pub error: Option<String>,
+    pub settings: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettingSource {
+    /// Dotted path of the key, e.g. `env.ANTHROPIC_BASE_URL`.
+    pub key: String,
+    /// The scope whose value is in effect. For arrays, which are combined across scopes,
+    /// this is the highest scope that contributed entries.
+    pub scope: String,
+    /// Every scope that sets the key, lowest precedence first.
+    pub defined_in: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EffectiveSettings {
+    pub project_id: String,
+    pub project_path: String,
+    /// Layers in merge order, lowest precedence first.
+    pub layers: Vec<SettingsLayer>,
+    pub settings: serde_json::Value,
+    pub sources: Vec<SettingSource>,
+}
+
+/// Where the managed (enterprise) policy file lives on this platform.
+pub fn managed_settings_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-settings.json")
+    } else if cfg!(target_os = "windows") {
+        PathBuf::from(r"C:\ProgramData\ClaudeCode\managed-settings.json")
+    } else {
+        PathBuf::from("/etc/claude-code/managed-settings.json")
+    }
+}
+
+/// The settings files Claude Code reads for `project_path`, lowest precedence first.
+pub fn layer_paths(project_path: &Path) -> Result<Vec<(&'static str, PathBuf)>, String> {
+    let home_dir = dirs::home_dir().ok_or("Failed to get home directory")?;
+    let claude_dir = project_path.join(".claude");
+    Ok(vec![
+        ("user", home_dir.join(".claude").join("settings.json")),
+        ("project", claude_dir.join("settings.json")),
+        ("local", claude_dir.join("settings.local.json")),
+        // 企业策略优先级最高，不能被其他层覆盖
+        ("enterprise", managed_settings_path()),
+    ])
+}
+
+pub fn load_layers(project_path: &Path) -> Result<Vec<SettingsLayer>, String> {
+    Ok(layer_paths(project_path)?
+        .into_iter()
+        .map(|(scope, path)| {
+            let exists = path.exists();
+            let (settings, error) = if exists {
+                match read_settings(&path) {
+                    Ok(settings) => (Some(settings), None),
+                    Err(e) => (None, Some(e)),
+                }
+            } else {
+                (None, None)
+            };
+            SettingsLayer {
+                scope: scope.to_string(),
+                path: path.to_string_lossy().to_string(),
+                exists,
+                error,
+                settings,
+            }
+        })
+        .collect())
+}
+
+fn record_source(sources: &mut BTreeMap<String, SettingSource>, key: &str, scope: &str) {
+    let source = sources.entry(key.to_string()).or_insert_with(|| SettingSource {
+        key: key.to_string(),
+        scope: scope.to_string(),
+        defined_in: Vec::new(),
+    });
+    source.scope = scope.to_string();
+    source.defined_in.push(scope.to_string());
+}
+
+// 值的类型在对象和非对象之间变化时，被替换掉的值的来源记录也要一并清除
+fn clear_sources(sources: &mut BTreeMap<String, SettingSource>, key: &str) {
+    let nested = format!("{}.", key);
+    sources.retain(|k, _| k != key && !k.starts_with(&nested));
+}
+
+// 对象逐键合并，数组（如 permissions.allow）跨层拼接去重，其余值由高优先级覆盖
+fn merge_into(
+    target: &mut serde_json::Map<String, serde_json::Value>,
+    layer: &serde_json::Map<String, serde_json::Value>,
+    scope: &str,
+    prefix: &str,
+    sources: &mut BTreeMap<String, SettingSource>,
+) {
+    for (key, value) in layer {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match (target.get_mut(key), value) {
+            (Some(serde_json::Value::Object(existing)), serde_json::Value::Object(incoming)) => {
+                merge_into(existing, incoming, scope, &path, sources);
+            }
+            (Some(serde_json::Value::Array(existing)), serde_json::Value::Array(incoming)) => {
+                for item in incoming {
+                    if !existing.contains(item) {
+                        existing.push(item.clone());
+                    }
+                }
+                record_source(sources, &path, scope);
+            }
+            (existing, serde_json::Value::Object(incoming)) => {
+                if existing.is_some() {
+                    clear_sources(sources, &path);
+                }
+                let mut merged = serde_json::Map::new();
+                merge_into(&mut merged, incoming, scope, &path, sources);
+                target.insert(key.clone(), serde_json::Value::Object(merged));
+            }
+            (existing, value) => {
+                if existing.is_some_and(|e| e.is_object()) {
+                    clear_sources(sources, &path);
+                }
+                target.insert(key.clone(), value.clone());
+                record_source(sources, &path, scope);
+            }
+        }
+    }
+}
+
+/// Merges layers in order, returning the effective settings and where each key came from.
+pub fn merge_layers(layers: &[SettingsLayer]) -> (serde_json::Value, Vec<SettingSource>) {
+    let mut merged = serde_json::Map::new();
+    let mut sources = BTreeMap::new();
+    for layer in layers {
+        if let Some(serde_json::Value::Object(settings)) = &layer.settings {
+            merge_into(&mut merged, settings, &layer.scope, "", &mut sources);
+        }
+    }
+    (serde_json::Value::Object(merged), sources.into_values().collect())
+}
+
+/// Loads the user, project, local and enterprise settings for a stored project and merges them
+/// the way Claude Code does.
+#[tauri::command]
+pub async fn get_effective_settings(app: AppHandle, project_id: String) -> Result<EffectiveSettings, String> {
+    let project = {
+        let conn = db::get_database_connection(&app).map_err(|e| e.to_string())?;
+        get_project_by_id_internal(&conn, &project_id)?
+    }
+    .ok_or_else(|| format!("Project '{}' not found", project_id))?;
+    let project_path = expand_home_path(&project.path)?;
+
+    let layers = load_layers(&project_path)?;
+    let (settings, sources) = merge_layers(&layers);
+
+    Ok(EffectiveSettings {
+        project_id: project.id,
+        project_path: project_path.to_string_lossy().to_string(),
+        layers,
+        settings,
+        sources,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(scope: &str, settings: serde_json::Value) -> SettingsLayer {
+        SettingsLayer {
+            scope: scope.to_string(),
+            path: format!("/{}/settings.json", scope),
+            exists: true,
+            error: None,
+            settings: Some(settings),
+        }
+    }
+
+    fn source<'a>(sources: &'a [SettingSource], key: &str) -> Option<&'a SettingSource> {
+        sources.iter().find(|s| s.key == key)
+    }
+
+    #[test]
+    fn higher_layers_override_lower_ones() {
+        let layers = vec![
+            layer("user", serde_json::json!({ "model": "sonnet", "env": { "A": "user", "B": "user" } })),
+            layer("project", serde_json::json!({ "model": "opus", "env": { "B": "project" } })),
+            layer("local", serde_json::json!({ "env": { "B": "local" } })),
+            layer("enterprise", serde_json::json!({ "model": "haiku" })),
+        ];
+        let (settings, sources) = merge_layers(&layers);
+
+        assert_eq!(settings["model"], "haiku");
+        assert_eq!(settings["env"], serde_json::json!({ "A": "user", "B": "local" }));
+        let model = source(&sources, "model").unwrap();
+        assert_eq!(model.scope, "enterprise");
+        assert_eq!(model.defined_in, ["user", "project", "enterprise"]);
+        assert_eq!(source(&sources, "env.A").unwrap().scope, "user");
+        assert_eq!(source(&sources, "env.B").unwrap().defined_in, ["user", "project", "local"]);
+    }
+
+    #[test]
+    fn arrays_are_combined_across_layers() {
+        let layers = vec![
+            layer("user", serde_json::json!({ "permissions": { "allow": ["Read", "Bash(ls)"] } })),
+            layer("project", serde_json::json!({ "permissions": { "allow": ["Bash(ls)", "Edit"] } })),
+        ];
+        let (settings, sources) = merge_layers(&layers);
+
+        assert_eq!(settings["permissions"]["allow"], serde_json::json!(["Read", "Bash(ls)", "Edit"]));
+        assert_eq!(source(&sources, "permissions.allow").unwrap().scope, "project");
+    }
+
+    #[test]
+    fn layers_that_failed_to_load_are_skipped() {
+        let mut broken = layer("project", serde_json::json!({}));
+        broken.settings = None;
+        broken.error = Some("Failed to parse settings file".to_string());
+        let (settings, _) = merge_layers(&[layer("user", serde_json::json!({ "model": "sonnet" })), broken]);
+        assert_eq!(settings["model"], "sonnet");
+    }
+
+    #[test]
+    fn replacing_an_object_with_a_scalar_drops_its_entries() {
+        let layers = vec![
+            layer("user", serde_json::json!({ "statusLine": { "type": "command", "command": "status.sh" } })),
+            layer("project", serde_json::json!({ "statusLine": "off" })),
+        ];
+        let (settings, sources) = merge_layers(&layers);
+
+        assert_eq!(settings["statusLine"], "off");
+        assert!(source(&sources, "statusLine.type").is_none());
+        assert!(source(&sources, "statusLine.command").is_none());
+        assert_eq!(source(&sources, "statusLine").unwrap().defined_in, ["project"]);
+    }
+
+    #[test]
+    fn replacing_a_scalar_with_an_object_drops_the_scalar_entry() {
+        let layers = vec![
+            layer("user", serde_json::json!({ "statusLine": "off" })),
+            layer("local", serde_json::json!({ "statusLine": { "type": "command", "command": "status.sh" } })),
+        ];
+        let (settings, sources) = merge_layers(&layers);
+
+        assert_eq!(settings["statusLine"]["command"], "status.sh");
+        assert!(source(&sources, "statusLine").is_none());
+        assert_eq!(source(&sources, "statusLine.type").unwrap().scope, "local");
+    }
+}
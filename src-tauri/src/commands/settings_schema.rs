@@ -0,0 +1,124 @@
+// src-tauri/src/commands/settings_schema.rs
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+const SETTINGS_SCHEMA: &str = include_str!("../../schemas/claude-settings.schema.json");
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettingsValidationError {
+    /// JSON pointer to the offending value, e.g. `/permissions/defaultMode`; empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+fn validator() -> Result<&'static jsonschema::Validator, String> {
+    static VALIDATOR: OnceLock<Result<jsonschema::Validator, String>> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let schema: serde_json::Value = serde_json::from_str(SETTINGS_SCHEMA)
+            .map_err(|e| format!("Bundled settings schema is invalid: {}", e))?;
+        jsonschema::validator_for(&schema).map_err(|e| format!("Bundled settings schema is invalid: {}", e))
+    }).as_ref().map_err(|e| e.clone())
+}
+
+pub fn collect_settings_errors(settings: &serde_json::Value) -> Result<Vec<SettingsValidationError>, String> {
+    Ok(validator()?
+        .iter_errors(settings)
+        .map(|e| SettingsValidationError {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect())
+}
+
+pub fn describe_settings_errors(errors: &[SettingsValidationError]) -> String {
+    let details: Vec<String> = errors.iter()
+        .map(|e| format!("{}: {}", if e.path.is_empty() { "/" } else { &e.path }, e.message))
+        .collect();
+    format!("Invalid settings: {}", details.join("; "))
+}
+
+/// Rejects settings that do not match the schema. Unknown keys are allowed.
+pub fn check_settings(settings: &serde_json::Value) -> Result<(), String> {
+    let errors = collect_settings_errors(settings)?;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(describe_settings_errors(&errors))
+    }
+}
+
+#[tauri::command]
+pub fn validate_claude_settings(content: String) -> Result<Vec<SettingsValidationError>, String> {
+    let settings: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("无效的JSON格式: {}", e))?;
+    collect_settings_errors(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = include_str!("../../tests/fixtures/settings/settings.json");
+
+    fn error_paths(settings: serde_json::Value) -> Vec<String> {
+        collect_settings_errors(&settings).unwrap().into_iter().map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn accepts_a_full_settings_file_with_unknown_keys() {
+        let settings: serde_json::Value = serde_json::from_str(SETTINGS).unwrap();
+        assert_eq!(check_settings(&settings), Ok(()));
+        assert_eq!(check_settings(&serde_json::json!({})), Ok(()));
+    }
+
+    #[test]
+    fn reports_an_unknown_default_mode_at_its_path() {
+        let paths = error_paths(serde_json::json!({ "permissions": { "defaultMode": "yolo" } }));
+        assert_eq!(paths, ["/permissions/defaultMode"]);
+    }
+
+    #[test]
+    fn reports_malformed_hooks_at_their_path() {
+        assert_eq!(error_paths(serde_json::json!({ "hooks": { "PreToolUse": { "matcher": "Bash" } } })), ["/hooks/PreToolUse"]);
+
+        let paths = error_paths(serde_json::json!({
+            "hooks": { "PreToolUse": [{ "matcher": "Bash", "hooks": [{ "type": "command" }] }] }
+        }));
+        assert_eq!(paths, ["/hooks/PreToolUse/0/hooks/0"]);
+    }
+
+    #[test]
+    fn hook_timeouts_must_be_whole_seconds() {
+        let hooks = |timeout: serde_json::Value| serde_json::json!({
+            "hooks": { "PreToolUse": [{ "matcher": "Bash", "hooks": [{ "type": "command", "command": "lint", "timeout": timeout }] }] }
+        });
+
+        assert_eq!(error_paths(hooks(serde_json::json!(1.5))), ["/hooks/PreToolUse/0/hooks/0/timeout"]);
+        assert_eq!(error_paths(hooks(serde_json::json!(0))), ["/hooks/PreToolUse/0/hooks/0/timeout"]);
+
+        // 通过校验的值也必须能读进 HookCommand
+        let valid = hooks(serde_json::json!(30));
+        assert_eq!(check_settings(&valid), Ok(()));
+        let settings: crate::models::ClaudeSettings = serde_json::from_value(valid).unwrap();
+        assert_eq!(settings.hooks.unwrap()["PreToolUse"][0].hooks[0].timeout, Some(30));
+    }
+
+    #[test]
+    fn reports_status_line_errors_at_their_path() {
+        assert_eq!(error_paths(serde_json::json!({ "statusLine": { "type": "command" } })), ["/statusLine"]);
+        assert_eq!(error_paths(serde_json::json!({ "statusLine": { "type": "command", "command": "" } })), ["/statusLine/command"]);
+        assert_eq!(error_paths(serde_json::json!({ "statusLine": { "type": "static", "command": "x", "padding": -1 } })), ["/statusLine/type", "/statusLine/padding"]);
+    }
+
+    #[test]
+    fn error_messages_name_every_path() {
+        let errors = collect_settings_errors(&serde_json::json!({
+            "model": 3,
+            "permissions": { "defaultMode": "yolo" }
+        })).unwrap();
+        let message = describe_settings_errors(&errors);
+        assert!(message.contains("/model: "), "{}", message);
+        assert!(message.contains("/permissions/defaultMode: "), "{}", message);
+    }
+}
@@ -0,0 +1,586 @@
+// src-tauri/src/commands/transformers.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::router::{get_router_config_path_with_custom, load_router_config, update_router_config};
+use crate::models::{ClaudeCodeRouterConfig, CustomTransformer, Provider, Transformer};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomTransformerInfo {
+    /// The name providers use to reference the transformer in their `use` chains. Entries
+    /// written by hand usually leave it to the plugin; `None` when neither declares one.
+    pub name: Option<String>,
+    /// `None` for legacy `{ "name", "args" }` entries that have no plugin file.
+    pub path: Option<String>,
+    pub options: Option<HashMap<String, serde_json::Value>>,
+    pub exists: bool,
+    pub readable: bool,
+    /// Providers (or `provider/model` entries) whose chains reference this transformer.
+    pub referenced_by: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCustomTransformerRequest {
+    /// Must match the `name` the plugin class declares, since CCR registers it under that name.
+    pub name: String,
+    pub path: String,
+    pub options: Option<HashMap<String, serde_json::Value>>,
+    /// `copy` or `symlink` to install the file into the router's `plugins` directory.
+    pub install: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateCustomTransformerRequest {
+    pub path: Option<String>,
+    pub options: Option<HashMap<String, serde_json::Value>>,
+    pub install: Option<String>,
+}
+
+/// Transformer names referenced by a `use` chain; entries are either `"name"` or `["name", options]`.
+pub fn chain_names(chain: &[serde_json::Value]) -> impl Iterator<Item = &str> {
+    chain.iter().filter_map(|entry| match entry {
+        serde_json::Value::String(name) => Some(name.as_str()),
+        serde_json::Value::Array(items) => items.first().and_then(|n| n.as_str()),
+        _ => None,
+    })
+}
+
+fn references(provider: &Provider, name: &str) -> Vec<String> {
+    let Some(transformer) = &provider.transformer else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    if chain_names(&transformer.use_transformers).any(|n| n == name) {
+        found.push(provider.name.clone());
+    }
+    for (model, entry) in &transformer.model_specific {
+        let chain = entry["use"].as_array().map(|c| c.as_slice()).unwrap_or_default();
+        if chain_names(chain).any(|n| n == name) {
+            found.push(format!("{}/{}", provider.name, model));
+        }
+    }
+    found
+}
+
+/// The `name` a CCR plugin class declares, e.g. `name = "custom";` or `this.name = 'custom'`.
+fn declared_name(source: &str) -> Option<String> {
+    let mut rest = source;
+    while let Some(found) = rest.find("name") {
+        let before = rest[..found].chars().next_back();
+        let after = rest[found + 4..].trim_start();
+        rest = &rest[found + 4..];
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').or_else(|| after.strip_prefix(':')) else {
+            continue;
+        };
+        let value = value.trim_start();
+        let Some(quote) = value.chars().next().filter(|c| matches!(c, '"' | '\'' | '`')) else {
+            continue;
+        };
+        if let Some(end) = value[1..].find(quote) {
+            return Some(value[1..1 + end].to_string()).filter(|n| !n.is_empty());
+        }
+    }
+    None
+}
+
+/// The name chains use for `transformer`: the one in the config, else the one its plugin declares.
+fn transformer_name(transformer: &CustomTransformer) -> Option<String> {
+    transformer.name.clone().or_else(|| {
+        let path = crate::commands::config::expand_home_path(transformer.path.as_deref()?).ok()?;
+        declared_name(&fs::read_to_string(path).ok()?)
+    })
+}
+
+fn describe(config: &ClaudeCodeRouterConfig, transformer: &CustomTransformer) -> CustomTransformerInfo {
+    let path = transformer.path.as_deref().map(Path::new);
+    let name = transformer_name(transformer);
+
+    CustomTransformerInfo {
+        referenced_by: name.as_deref()
+            .map(|name| config.providers.iter().flat_map(|p| references(p, name)).collect())
+            .unwrap_or_default(),
+        name,
+        path: transformer.path.clone(),
+        options: transformer.options.clone(),
+        exists: path.is_some_and(|p| p.is_file()),
+        readable: path.is_some_and(|p| fs::File::open(p).is_ok()),
+    }
+}
+
+fn validate_plugin_file(path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("Transformer file not found: {}", path.display()));
+    }
+    if path.extension().and_then(|e| e.to_str()) != Some("js") {
+        return Err("Transformer plugins must be JavaScript (.js) files".to_string());
+    }
+    fs::File::open(path).map_err(|e| format!("Transformer file is not readable: {}", e))?;
+    Ok(())
+}
+
+async fn plugins_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_path = get_router_config_path_with_custom(Some(app)).await?;
+    let config_dir = config_path.parent().ok_or("Failed to resolve router config directory")?;
+    Ok(config_dir.join("plugins"))
+}
+
+/// Copies or symlinks `source` into the plugins directory and returns the installed path,
+/// or `None` when `source` already is in the plugins directory.
+fn install_plugin(source: &Path, plugins_dir: &Path, mode: &str) -> Result<Option<PathBuf>, String> {
+    let file_name = source.file_name().ok_or("Invalid transformer path")?;
+    let target = plugins_dir.join(file_name);
+    if target == source {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(plugins_dir)
+        .map_err(|e| format!("Failed to create plugins directory: {}", e))?;
+    if target.exists() || target.is_symlink() {
+        return Err(format!("A plugin named {:?} already exists in {}", file_name, plugins_dir.display()));
+    }
+
+    match mode {
+        "copy" => {
+            fs::copy(source, &target).map_err(|e| format!("Failed to copy transformer: {}", e))?;
+        }
+        "symlink" => {
+            let source = source.canonicalize().map_err(|e| e.to_string())?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&source, &target)
+                .map_err(|e| format!("Failed to symlink transformer: {}", e))?;
+            #[cfg(windows)]
+            std::os::windows::fs::symlink_file(&source, &target)
+                .map_err(|e| format!("Failed to symlink transformer: {}", e))?;
+        }
+        other => return Err(format!("Unknown install mode '{}', expected copy or symlink", other)),
+    }
+
+    Ok(Some(target))
+}
+
+/// A plugin file to register, and the copy or symlink created for it, if any.
+struct PreparedPath {
+    path: String,
+    installed: Option<PathBuf>,
+}
+
+impl PreparedPath {
+    // 配置保存失败时删除刚安装的插件文件，避免留下没有注册的插件
+    fn discard(self) {
+        if let Some(installed) = self.installed {
+            if let Err(e) = fs::remove_file(&installed) {
+                eprintln!("Failed to remove installed plugin {:?}: {}", installed, e);
+            }
+        }
+    }
+}
+
+async fn prepare_path(app: &AppHandle, path: &str, install: Option<&str>) -> Result<PreparedPath, String> {
+    let path = crate::commands::config::expand_home_path(path)?;
+    validate_plugin_file(&path)?;
+
+    let installed = match install {
+        Some(mode) => install_plugin(&path, &plugins_dir(app).await?, mode)?,
+        None => None,
+    };
+    Ok(PreparedPath {
+        path: installed.as_deref().unwrap_or(&path).to_string_lossy().to_string(),
+        installed,
+    })
+}
+
+/// Finds a transformer by its name (from the config or its plugin), or by its path.
+fn find_index(config: &ClaudeCodeRouterConfig, key: &str) -> Result<usize, String> {
+    config.transformers.iter().flatten()
+        .position(|t| transformer_name(t).as_deref() == Some(key) || t.path.as_deref() == Some(key))
+        .ok_or_else(|| format!("Transformer '{}' not found", key))
+}
+
+fn check_unreferenced(config: &ClaudeCodeRouterConfig, index: usize) -> Result<(), String> {
+    let info = describe(config, &config.transformers.as_ref().ok_or("No transformers registered")?[index]);
+    if !info.referenced_by.is_empty() {
+        return Err(format!(
+            "Transformer '{}' is still used by: {}",
+            info.name.unwrap_or_default(),
+            info.referenced_by.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_custom_transformers(app: AppHandle) -> Result<Vec<CustomTransformerInfo>, String> {
+    let config = load_router_config(&app).await?;
+    Ok(config.transformers.iter().flatten().map(|t| describe(&config, t)).collect())
+}
+
+#[tauri::command]
+pub async fn add_custom_transformer(app: AppHandle, request: CreateCustomTransformerRequest) -> Result<CustomTransformerInfo, String> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Transformer name is required".to_string());
+    }
+
+    let mut config = load_router_config(&app).await?;
+    // 先检查重名，避免安装了插件文件之后才发现无法注册
+    if find_index(&config, &name).is_ok() {
+        return Err(format!("Transformer '{}' is already registered", name));
+    }
+
+    let prepared = prepare_path(&app, &request.path, request.install.as_deref()).await?;
+    // CCR 按插件里声明的 name 注册，名字不一致时链里的引用永远找不到它
+    if let Some(declared) = fs::read_to_string(&prepared.path).ok().and_then(|s| declared_name(&s)) {
+        if declared != name {
+            prepared.discard();
+            return Err(format!("The plugin declares the name '{}', not '{}'", declared, name));
+        }
+    }
+    let transformer = CustomTransformer {
+        name: Some(name),
+        path: Some(prepared.path.clone()),
+        options: request.options,
+        extra: serde_json::Map::new(),
+    };
+    config.transformers.get_or_insert_with(Vec::new).push(transformer.clone());
+    let info = describe(&config, &transformer);
+
+    if let Err(e) = update_router_config(app, config, None).await {
+        prepared.discard();
+        return Err(e);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn update_custom_transformer(app: AppHandle, name: String, request: UpdateCustomTransformerRequest) -> Result<CustomTransformerInfo, String> {
+    let mut config = load_router_config(&app).await?;
+    let index = find_index(&config, &name)?;
+
+    let prepared = match &request.path {
+        Some(path) => Some(prepare_path(&app, path, request.install.as_deref()).await?),
+        None => None,
+    };
+
+    let transformers = config.transformers.get_or_insert_with(Vec::new);
+    if let Some(prepared) = &prepared {
+        transformers[index].path = Some(prepared.path.clone());
+    }
+    if let Some(options) = request.options {
+        transformers[index].options = Some(options);
+    }
+    let updated = transformers[index].clone();
+
+    let info = describe(&config, &updated);
+    if let Err(e) = update_router_config(app, config, None).await {
+        if let Some(prepared) = prepared {
+            prepared.discard();
+        }
+        return Err(e);
+    }
+    Ok(info)
+}
+
+/// Unregisters a transformer. Refused while any provider chain still references it.
+#[tauri::command]
+pub async fn remove_custom_transformer(app: AppHandle, name: String) -> Result<bool, String> {
+    let mut config = load_router_config(&app).await?;
+    let index = find_index(&config, &name)?;
+    check_unreferenced(&config, index)?;
+
+    let transformers = config.transformers.get_or_insert_with(Vec::new);
+    transformers.remove(index);
+    if transformers.is_empty() {
+        config.transformers = None;
+    }
+    update_router_config(app, config, None).await?;
+
+    Ok(true)
+}
+
+/// The chain to edit: the provider-wide `use` list, or the `use` list of one model.
+fn chain_mut<'a>(provider: &'a mut Provider, model: Option<&str>) -> Result<&'a mut Vec<serde_json::Value>, String> {
+    if let Some(model) = model {
+        if !provider.models.iter().any(|m| m == model) {
+            return Err(format!("Model '{}' is not declared for provider '{}'", model, provider.name));
+        }
+    }
+
+    let transformer = provider.transformer.get_or_insert_with(Transformer::default);
+    let Some(model) = model else {
+        return Ok(&mut transformer.use_transformers);
+    };
+
+    let entry = transformer.model_specific
+        .entry(model.to_string())
+        .or_insert_with(|| serde_json::json!({ "use": [] }));
+    if !entry.is_object() {
+        *entry = serde_json::json!({ "use": [] });
+    }
+    let object = entry.as_object_mut().ok_or("Invalid model transformer entry")?;
+    let chain = object.entry("use").or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    if !chain.is_array() {
+        *chain = serde_json::Value::Array(Vec::new());
+    }
+    chain.as_array_mut().ok_or_else(|| "Invalid model transformer chain".to_string())
+}
+
+// 删除后清理空的模型条目和空的 transformer，避免写出无意义的配置
+fn prune_empty_chains(provider: &mut Provider) {
+    let Some(transformer) = provider.transformer.as_mut() else {
+        return;
+    };
+    transformer.model_specific.retain(|_, entry| {
+        let Some(object) = entry.as_object() else { return true };
+        let empty_chain = object.get("use").and_then(|c| c.as_array()).is_some_and(|c| c.is_empty());
+        !(empty_chain && object.len() == 1)
+    });
+    if transformer.use_transformers.is_empty() && transformer.model_specific.is_empty() {
+        provider.transformer = None;
+    }
+}
+
+async fn edit_provider_chain<F>(app: AppHandle, provider_name: &str, model: Option<&str>, edit: F) -> Result<Option<Transformer>, String>
+where
+    F: FnOnce(&mut Vec<serde_json::Value>) -> Result<(), String>,
+{
+    let mut config = load_router_config(&app).await?;
+    let provider = config.providers.iter_mut()
+        .find(|p| p.name == provider_name)
+        .ok_or_else(|| format!("Provider '{}' not found", provider_name))?;
+
+    edit(chain_mut(provider, model)?)?;
+    prune_empty_chains(provider);
+
+    let transformer = provider.transformer.clone();
+    update_router_config(app, config, None).await?;
+    Ok(transformer)
+}
+
+fn check_index(chain: &[serde_json::Value], index: usize) -> Result<(), String> {
+    if index >= chain.len() {
+        return Err(format!("Transformer index {} is out of range (chain has {} entries)", index, chain.len()));
+    }
+    Ok(())
+}
+
+fn insert_entry(chain: &mut Vec<serde_json::Value>, position: Option<usize>, entry: serde_json::Value) -> Result<(), String> {
+    let position = position.unwrap_or(chain.len());
+    if position > chain.len() {
+        return Err(format!("Position {} is out of range (chain has {} entries)", position, chain.len()));
+    }
+    chain.insert(position, entry);
+    Ok(())
+}
+
+fn remove_entry(chain: &mut Vec<serde_json::Value>, index: usize) -> Result<(), String> {
+    check_index(chain, index)?;
+    chain.remove(index);
+    Ok(())
+}
+
+fn move_entry(chain: &mut Vec<serde_json::Value>, from: usize, to: usize) -> Result<(), String> {
+    check_index(chain, from)?;
+    check_index(chain, to)?;
+    let entry = chain.remove(from);
+    chain.insert(to, entry);
+    Ok(())
+}
+
+/// Adds `name` to a provider's chain (or to `model`'s chain), optionally with an options
+/// object, which is written as `["name", options]`.
+#[tauri::command]
+pub async fn add_provider_transformer(
+    app: AppHandle,
+    provider_name: String,
+    model: Option<String>,
+    name: String,
+    options: Option<serde_json::Value>,
+    position: Option<usize>,
+) -> Result<Option<Transformer>, String> {
+    let entry = match options {
+        Some(options) if !options.is_null() => serde_json::json!([name, options]),
+        _ => serde_json::Value::String(name),
+    };
+
+    edit_provider_chain(app, &provider_name, model.as_deref(), |chain| insert_entry(chain, position, entry)).await
+}
+
+#[tauri::command]
+pub async fn remove_provider_transformer(
+    app: AppHandle,
+    provider_name: String,
+    model: Option<String>,
+    index: usize,
+) -> Result<Option<Transformer>, String> {
+    edit_provider_chain(app, &provider_name, model.as_deref(), |chain| remove_entry(chain, index)).await
+}
+
+/// Moves the entry at `from` so that it ends up at `to`.
+#[tauri::command]
+pub async fn reorder_provider_transformers(
+    app: AppHandle,
+    provider_name: String,
+    model: Option<String>,
+    from: usize,
+    to: usize,
+) -> Result<Option<Transformer>, String> {
+    edit_provider_chain(app, &provider_name, model.as_deref(), |chain| move_entry(chain, from, to)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transformers_are_found_by_name_or_by_path_when_unnamed() {
+        let config = ClaudeCodeRouterConfig::from_json_str(include_str!("../../tests/fixtures/router/legacy_transformers.json")).unwrap();
+
+        assert_eq!(find_index(&config, "enhancetool"), Ok(1));
+        assert_eq!(find_index(&config, "/home/xxx/.claude-code-router/plugins/custom.js"), Ok(2));
+        // 文件名不是转换器名，插件文件也不存在
+        assert!(find_index(&config, "custom").is_err());
+    }
+
+    #[test]
+    fn unnamed_entries_use_the_name_their_plugin_declares() {
+        let dir = std::env::temp_dir().join(format!("claude-meta-transformers-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let plugin = dir.join("custom.js");
+        fs::write(&plugin, "class Custom {\n  filename = 'x.js';\n  name = \"custom-tx\";\n}\nmodule.exports = Custom;\n").unwrap();
+
+        let mut config = ClaudeCodeRouterConfig::from_json_str(include_str!("../../tests/fixtures/router/legacy_transformers.json")).unwrap();
+        config.transformers.as_mut().unwrap()[2].path = Some(plugin.to_string_lossy().to_string());
+        config.providers[0].transformer.as_mut().unwrap().model_specific.insert(
+            "anthropic/claude-sonnet-4".to_string(),
+            serde_json::json!({ "use": [["custom-tx", { "level": 1 }]] }),
+        );
+
+        assert_eq!(find_index(&config, "custom-tx"), Ok(2));
+        let info = describe(&config, &config.transformers.as_ref().unwrap()[2]);
+        assert_eq!(info.name.as_deref(), Some("custom-tx"));
+        assert_eq!(info.referenced_by, vec!["openrouter/anthropic/claude-sonnet-4".to_string()]);
+        assert!(check_unreferenced(&config, 2).unwrap_err().contains("openrouter/anthropic/claude-sonnet-4"));
+
+        config.providers[0].transformer.as_mut().unwrap().model_specific.clear();
+        assert!(check_unreferenced(&config, 2).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn removal_is_refused_while_a_provider_chain_uses_the_transformer() {
+        let config = ClaudeCodeRouterConfig::from_json_str(include_str!("../../tests/fixtures/router/legacy_transformers.json")).unwrap();
+        let mut with_reference = config.clone();
+        with_reference.providers[0].transformer.as_mut().unwrap().use_transformers.push(serde_json::json!("enhancetool"));
+
+        assert!(check_unreferenced(&config, 1).is_ok());
+        assert_eq!(
+            check_unreferenced(&with_reference, 1),
+            Err("Transformer 'enhancetool' is still used by: openrouter".to_string())
+        );
+    }
+
+    #[test]
+    fn declared_names_are_read_from_plugin_source() {
+        assert_eq!(declared_name("class A { name = 'a'; }").as_deref(), Some("a"));
+        assert_eq!(declared_name("constructor() { this.name = \"b\"; }").as_deref(), Some("b"));
+        assert_eq!(declared_name("module.exports = { name: `c` };").as_deref(), Some("c"));
+        assert_eq!(declared_name("const filename = 'x'; const name = value;"), None);
+    }
+
+    fn deepseek(transformer: serde_json::Value) -> Provider {
+        serde_json::from_value(serde_json::json!({
+            "name": "deepseek",
+            "api_base_url": "https://api.deepseek.com/chat/completions",
+            "api_key": "sk-xxx",
+            "models": ["deepseek-chat", "deepseek-reasoner"],
+            "transformer": transformer,
+        })).unwrap()
+    }
+
+    #[test]
+    fn chain_indexes_are_bounds_checked() {
+        let mut chain = vec![serde_json::json!("deepseek"), serde_json::json!("tooluse"), serde_json::json!(["maxtoken", { "max_tokens": 8192 }])];
+
+        assert!(move_entry(&mut chain, 3, 0).is_err());
+        assert!(move_entry(&mut chain, 0, 3).is_err());
+        assert!(remove_entry(&mut chain, 3).is_err());
+        assert!(insert_entry(&mut chain, Some(4), serde_json::json!("x")).is_err());
+        assert_eq!(chain.len(), 3);
+
+        move_entry(&mut chain, 2, 0).unwrap();
+        assert_eq!(chain_names(&chain).collect::<Vec<_>>(), ["maxtoken", "deepseek", "tooluse"]);
+        move_entry(&mut chain, 0, 2).unwrap();
+        assert_eq!(chain_names(&chain).collect::<Vec<_>>(), ["deepseek", "tooluse", "maxtoken"]);
+        remove_entry(&mut chain, 1).unwrap();
+        assert!(insert_entry(&mut chain, Some(3), serde_json::json!("enhancetool")).is_err());
+        insert_entry(&mut chain, None, serde_json::json!("enhancetool")).unwrap();
+        assert_eq!(chain_names(&chain).collect::<Vec<_>>(), ["deepseek", "maxtoken", "enhancetool"]);
+    }
+
+    #[test]
+    fn model_chains_are_only_created_for_declared_models() {
+        let mut provider = deepseek(serde_json::json!({ "use": ["deepseek"] }));
+
+        assert!(chain_mut(&mut provider, Some("gpt-4o")).is_err());
+        assert!(!provider.transformer.as_ref().unwrap().model_specific.contains_key("gpt-4o"));
+
+        chain_mut(&mut provider, Some("deepseek-chat")).unwrap().push(serde_json::json!("tooluse"));
+        let transformer = provider.transformer.as_ref().unwrap();
+        assert_eq!(transformer.model_specific["deepseek-chat"], serde_json::json!({ "use": ["tooluse"] }));
+        assert_eq!(chain_names(&transformer.use_transformers).collect::<Vec<_>>(), ["deepseek"]);
+    }
+
+    #[test]
+    fn pruning_drops_empty_model_chains_but_keeps_the_provider_chain() {
+        let mut provider = deepseek(serde_json::json!({
+            "use": ["deepseek"],
+            "deepseek-chat": { "use": ["tooluse"] },
+            "deepseek-reasoner": { "use": ["reasoning"], "note": "kept" }
+        }));
+
+        remove_entry(chain_mut(&mut provider, Some("deepseek-chat")).unwrap(), 0).unwrap();
+        remove_entry(chain_mut(&mut provider, Some("deepseek-reasoner")).unwrap(), 0).unwrap();
+        prune_empty_chains(&mut provider);
+
+        let transformer = provider.transformer.as_ref().unwrap();
+        assert!(!transformer.model_specific.contains_key("deepseek-chat"));
+        // 还有其他字段的模型条目保留
+        assert_eq!(transformer.model_specific["deepseek-reasoner"], serde_json::json!({ "use": [], "note": "kept" }));
+        assert_eq!(chain_names(&transformer.use_transformers).collect::<Vec<_>>(), ["deepseek"]);
+
+        let mut only_model = deepseek(serde_json::json!({ "deepseek-chat": { "use": ["tooluse"] } }));
+        remove_entry(chain_mut(&mut only_model, Some("deepseek-chat")).unwrap(), 0).unwrap();
+        prune_empty_chains(&mut only_model);
+        assert!(only_model.transformer.is_none());
+    }
+
+    #[test]
+    fn discarding_removes_only_newly_installed_plugins() {
+        let dir = std::env::temp_dir().join(format!("claude-meta-transformers-{}", uuid::Uuid::new_v4()));
+        let plugins = dir.join("plugins");
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("custom.js");
+        fs::write(&source, "module.exports = class {};").unwrap();
+
+        let installed = install_plugin(&source, &plugins, "copy").unwrap();
+        assert_eq!(installed.as_deref(), Some(plugins.join("custom.js").as_path()));
+        PreparedPath { path: String::new(), installed }.discard();
+        assert!(!plugins.join("custom.js").exists());
+        assert!(source.exists());
+
+        let in_place = install_plugin(&source, &dir, "copy").unwrap();
+        assert_eq!(in_place, None);
+        PreparedPath { path: String::new(), installed: in_place }.discard();
+        assert!(source.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -145,23 +145,14 @@ pub async fn install_feature(feature_id: String) -> Result<bool, String> {
             
             // Create default router configuration
             let default_config = serde_json::json!({
-                "anthropic_api_key": null,
-                "proxy_url": null,
-                "log": false,
-                "host": "localhost",
-                "non_interactive_mode": false,
-                "api_timeout_ms": 600000,
-                "custom_router_path": null,
-                "providers": [],
-                "router": {
-                    "default": null,
-                    "background": null,
-                    "think": null,
-                    "long_context": null,
-                    "long_context_threshold": 60000,
-                    "web_search": null
-                },
-                "transformers": null
+                "LOG": false,
+                "HOST": "localhost",
+                "NON_INTERACTIVE_MODE": false,
+                "API_TIMEOUT_MS": 600000,
+                "Providers": [],
+                "Router": {
+                    "longContextThreshold": 60000
+                }
             });
             
             let content = serde_json::to_string_pretty(&default_config)
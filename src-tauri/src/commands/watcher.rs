@@ -0,0 +1,354 @@
+// src-tauri/src/commands/watcher.rs
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::crypto;
+use crate::commands::config::expand_home_path;
+use crate::commands::config_path::get_config_path;
+use crate::commands::router::get_router_config_path_with_custom;
+
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-file-changed";
+pub const ROUTER_CONFIG_CHANGED_EVENT: &str = "router-config-changed";
+
+/// Holds the active watcher; replacing it stops watching the previous paths.
+#[derive(Default)]
+pub struct ConfigWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettingsSummary {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub allow_rules: usize,
+    pub deny_rules: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterConfigSummary {
+    pub providers: Vec<String>,
+    pub default_route: Option<String>,
+    pub log: Option<bool>,
+}
+
+/// The new file content with secrets masked, plus a parsed summary. `content` is `None` when
+/// the file was deleted or is not valid JSON, since secrets cannot be located in that case.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigFileChangedEvent<T> {
+    pub path: String,
+    pub deleted: bool,
+    pub content: Option<String>,
+    pub summary: Option<T>,
+    pub parse_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WatchedFile {
+    Settings,
+    RouterConfig,
+}
+
+fn summarize_settings(value: &serde_json::Value) -> SettingsSummary {
+    let env = &value["env"];
+    let rule_count = |list: &str| value["permissions"][list].as_array().map_or(0, |rules| rules.len());
+
+    SettingsSummary {
+        api_key: env["ANTHROPIC_API_KEY"].as_str()
+            .or_else(|| env["ANTHROPIC_AUTH_TOKEN"].as_str())
+            .map(crypto::mask_secret),
+        base_url: env["ANTHROPIC_BASE_URL"].as_str().map(|s| s.to_string()),
+        model: value["model"].as_str().map(|s| s.to_string()),
+        allow_rules: rule_count("allow"),
+        deny_rules: rule_count("deny"),
+    }
+}
+
+fn summarize_router_config(value: &serde_json::Value) -> RouterConfigSummary {
+    // 兼容 CCR 官方的大写字段和旧版本的小写字段
+    let providers = value.get("Providers").or_else(|| value.get("providers"));
+    let router = value.get("Router").or_else(|| value.get("router"));
+
+    RouterConfigSummary {
+        providers: providers
+            .and_then(|p| p.as_array())
+            .map(|list| list.iter().filter_map(|p| p["name"].as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default(),
+        default_route: router.and_then(|r| r["default"].as_str()).map(|s| s.to_string()),
+        log: value.get("LOG").or_else(|| value.get("log")).and_then(|v| v.as_bool()),
+    }
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    ["KEY", "TOKEN", "SECRET", "PASSWORD"].iter().any(|marker| name.contains(marker))
+}
+
+fn mask_in_place(value: &mut serde_json::Value) {
+    // `$VAR` 引用不是密钥本身，保持原样
+    if let Some(secret) = value.as_str().filter(|s| !s.starts_with('$')) {
+        *value = serde_json::Value::String(crypto::mask_secret(secret));
+    }
+}
+
+fn redact_settings(value: &mut serde_json::Value) {
+    if let Some(env) = value.get_mut("env").and_then(|env| env.as_object_mut()) {
+        for (name, entry) in env.iter_mut() {
+            if is_secret_name(name) {
+                mask_in_place(entry);
+            }
+        }
+    }
+}
+
+fn redact_router_config(value: &mut serde_json::Value) {
+    for key in ["APIKEY", "ANTHROPIC_API_KEY", "anthropic_api_key"] {
+        if let Some(entry) = value.get_mut(key) {
+            mask_in_place(entry);
+        }
+    }
+    for key in ["Providers", "providers"] {
+        for provider in value.get_mut(key).and_then(|p| p.as_array_mut()).into_iter().flatten() {
+            if let Some(entry) = provider.get_mut("api_key") {
+                mask_in_place(entry);
+            }
+        }
+    }
+}
+
+fn change_event<T>(
+    path: &Path,
+    content: Option<&str>,
+    summarize: fn(&serde_json::Value) -> T,
+    redact: fn(&mut serde_json::Value),
+) -> ConfigFileChangedEvent<T> {
+    let (content_shown, summary, parse_error) = match content.map(serde_json::from_str::<serde_json::Value>) {
+        Some(Ok(mut value)) => {
+            let summary = summarize(&value);
+            redact(&mut value);
+            (serde_json::to_string_pretty(&value).ok(), Some(summary), None)
+        }
+        Some(Err(e)) => (None, None, Some(e.to_string())),
+        None => (None, None, None),
+    };
+
+    ConfigFileChangedEvent {
+        path: path.to_string_lossy().to_string(),
+        deleted: content.is_none(),
+        content: content_shown,
+        summary,
+        parse_error,
+    }
+}
+
+fn emit_change<T: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    path: &Path,
+    content: Option<&str>,
+    summarize: fn(&serde_json::Value) -> T,
+    redact: fn(&mut serde_json::Value),
+) {
+    let payload = change_event(path, content, summarize, redact);
+    if let Err(e) = app.emit(event, payload) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+/// Compares full paths, resolving the parent directory so that platforms reporting
+/// canonical paths (e.g. `/private/var` on macOS) still match.
+fn is_same_path(event_path: &Path, target: &Path) -> bool {
+    if event_path == target {
+        return true;
+    }
+    if event_path.file_name() != target.file_name() {
+        return false;
+    }
+    match (event_path.parent().map(fs::canonicalize), target.parent().map(fs::canonicalize)) {
+        (Some(Ok(a)), Some(Ok(b))) => a == b,
+        _ => false,
+    }
+}
+
+/// The directory that is actually watched for `target`: its parent, or the nearest
+/// ancestor that exists when the parent has not been created yet.
+fn watch_dir(target: &Path) -> Option<&Path> {
+    target.ancestors().skip(1).find(|dir| dir.is_dir())
+}
+
+fn handle_event(
+    app: &AppHandle,
+    targets: &[(PathBuf, WatchedFile)],
+    last_contents: &Mutex<HashMap<PathBuf, Option<String>>>,
+    event: Event,
+) {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+        return;
+    }
+
+    // 父目录不存在时监听的是上层目录；中间目录被创建后需要重新挂到更近的目录上
+    let created_ancestor = matches!(event.kind, EventKind::Create(_))
+        && event.paths.iter().any(|p| {
+            p.is_dir() && targets.iter().any(|(target, _)| target != p && target.starts_with(p))
+        });
+    if created_ancestor {
+        let handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = restart_config_watcher(&handle).await {
+                eprintln!("Failed to restart config watcher: {}", e);
+            }
+        });
+    }
+
+    for (target, kind) in targets {
+        // 编辑器通常以“写临时文件再重命名”的方式保存，所以监听的是父目录，这里按完整路径匹配
+        if !event.paths.iter().any(|p| is_same_path(p, target)) {
+            continue;
+        }
+
+        let content = fs::read_to_string(target).ok();
+
+        // 一次保存往往触发多个事件，内容没变就不再通知前端
+        {
+            let mut last = last_contents.lock().unwrap_or_else(|e| e.into_inner());
+            if last.get(target) == Some(&content) {
+                continue;
+            }
+            last.insert(target.clone(), content.clone());
+        }
+
+        match kind {
+            WatchedFile::Settings => emit_change(app, SETTINGS_CHANGED_EVENT, target, content.as_deref(), summarize_settings, redact_settings),
+            WatchedFile::RouterConfig => emit_change(app, ROUTER_CONFIG_CHANGED_EVENT, target, content.as_deref(), summarize_router_config, redact_router_config),
+        }
+    }
+}
+
+/// (Re)starts watching the active Claude settings file and the router config file.
+pub async fn restart_config_watcher(app: &AppHandle) -> Result<(), String> {
+    let settings_path = expand_home_path(&get_config_path(app.clone()).await?)?;
+    let router_path = get_router_config_path_with_custom(Some(app)).await?;
+
+    let targets = vec![
+        (settings_path, WatchedFile::Settings),
+        (router_path, WatchedFile::RouterConfig),
+    ];
+    let last_contents: Mutex<HashMap<PathBuf, Option<String>>> = Mutex::new(
+        targets.iter().map(|(path, _)| (path.clone(), fs::read_to_string(path).ok())).collect(),
+    );
+
+    let handle = app.clone();
+    let watched = targets.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        match result {
+            Ok(event) => handle_event(&handle, &watched, &last_contents, event),
+            Err(e) => eprintln!("Config watcher error: {}", e),
+        }
+    }).map_err(|e| format!("Failed to create config watcher: {}", e))?;
+
+    for (path, _) in &targets {
+        if let Some(dir) = watch_dir(path) {
+            watcher.watch(dir, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch {:?}: {}", dir, e))?;
+        }
+    }
+
+    let state = app.state::<ConfigWatcher>();
+    *state.watcher.lock().map_err(|e| e.to_string())? = Some(watcher);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn refresh_config_watcher(app: AppHandle) -> Result<bool, String> {
+    restart_config_watcher(&app).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_full_paths_only() {
+        let dir = std::env::temp_dir().join(format!("claude-meta-watch-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("other")).unwrap();
+        let target = dir.join("settings.json");
+
+        assert!(is_same_path(&dir.join("settings.json"), &target));
+        assert!(is_same_path(&dir.join("other").join("..").join("settings.json"), &target));
+        assert!(!is_same_path(&dir.join("other").join("settings.json"), &target));
+        assert!(!is_same_path(&dir.join("settings.local.json"), &target));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn watches_the_nearest_existing_ancestor() {
+        let dir = std::env::temp_dir().join(format!("claude-meta-watch-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join(".claude").join("settings.json");
+
+        assert_eq!(watch_dir(&target), Some(dir.as_path()));
+        fs::create_dir(dir.join(".claude")).unwrap();
+        assert_eq!(watch_dir(&target), Some(dir.join(".claude").as_path()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn settings_changes_carry_redacted_content_and_a_summary() {
+        let content = serde_json::json!({
+            "model": "opus",
+            "env": { "ANTHROPIC_API_KEY": "sk-ant-api03-secret", "ANTHROPIC_BASE_URL": "https://api.example.com" },
+            "permissions": { "allow": ["Bash(ls:*)"] }
+        }).to_string();
+
+        let payload = change_event(Path::new("/tmp/settings.json"), Some(&content), summarize_settings, redact_settings);
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(!json.contains("sk-ant-api03-secret"), "{}", json);
+
+        let shown: serde_json::Value = serde_json::from_str(payload.content.as_deref().unwrap()).unwrap();
+        assert_eq!(shown["env"]["ANTHROPIC_API_KEY"], crypto::mask_secret("sk-ant-api03-secret"));
+        assert_eq!(shown["env"]["ANTHROPIC_BASE_URL"], "https://api.example.com");
+        assert_eq!(shown["permissions"]["allow"][0], "Bash(ls:*)");
+        let summary = payload.summary.unwrap();
+        assert_eq!(summary.model.as_deref(), Some("opus"));
+        assert_eq!(summary.allow_rules, 1);
+    }
+
+    #[test]
+    fn router_changes_mask_provider_keys() {
+        let content = serde_json::json!({
+            "APIKEY": "router-secret-key",
+            "Providers": [
+                { "name": "deepseek", "api_key": "sk-deepseek-secret", "models": ["deepseek-chat"] },
+                { "name": "ollama", "api_key": "$OLLAMA_KEY", "models": ["qwen"] }
+            ],
+            "Router": { "default": "deepseek,deepseek-chat" }
+        }).to_string();
+
+        let payload = change_event(Path::new("/tmp/config.json"), Some(&content), summarize_router_config, redact_router_config);
+        let shown: serde_json::Value = serde_json::from_str(payload.content.as_deref().unwrap()).unwrap();
+        assert_eq!(shown["APIKEY"], crypto::mask_secret("router-secret-key"));
+        assert_eq!(shown["Providers"][0]["api_key"], crypto::mask_secret("sk-deepseek-secret"));
+        assert_eq!(shown["Providers"][1]["api_key"], "$OLLAMA_KEY");
+        assert_eq!(payload.summary.unwrap().providers, ["deepseek", "ollama"]);
+    }
+
+    #[test]
+    fn unparsable_or_deleted_files_carry_no_content() {
+        let invalid = change_event(Path::new("/tmp/settings.json"), Some("{ \"env\": { \"ANTHROPIC_API_KEY\": \"sk-x"), summarize_settings, redact_settings);
+        assert!(invalid.content.is_none());
+        assert!(invalid.parse_error.is_some());
+        assert!(!invalid.deleted);
+
+        let deleted = change_event(Path::new("/tmp/settings.json"), None, summarize_settings, redact_settings);
+        assert!(deleted.deleted);
+        assert!(deleted.content.is_none());
+        assert!(deleted.parse_error.is_none());
+    }
+}
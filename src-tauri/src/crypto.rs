@@ -0,0 +1,236 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::{Connection, OptionalExtension};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// 加密后的字段统一带上前缀，方便区分旧的明文数据
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const KEYRING_SERVICE: &str = "claude-meta";
+const KEYRING_ACCOUNT: &str = "master-key";
+// 旧版本把主密钥和数据库放在同一个目录里，只在迁移时读取
+const LEGACY_MASTER_KEY_FILE: &str = "master.key";
+const NONCE_LEN: usize = 12;
+
+static MASTER_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// Loads the master key from the OS keychain, generating and storing a new one on first use.
+///
+/// The key is never written next to the database, so a copy of the app data directory only
+/// contains ciphertext. A `master.key` file left in `legacy_dir` by older versions is moved
+/// into the keychain and deleted.
+pub fn init_master_key(legacy_dir: &Path) -> Result<(), String> {
+    if MASTER_KEY.get().is_some() {
+        return Ok(());
+    }
+
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_ACCOUNT)
+        .map_err(|e| format!("Failed to open the system keychain: {}", e))?;
+
+    let key = match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded)?,
+        Err(keyring::Error::NoEntry) => {
+            let legacy_path = legacy_dir.join(LEGACY_MASTER_KEY_FILE);
+            let key = if legacy_path.exists() {
+                let encoded = fs::read_to_string(&legacy_path)
+                    .map_err(|e| format!("Failed to read legacy master key: {}", e))?;
+                decode_key(&encoded)?
+            } else {
+                Aes256Gcm::generate_key(OsRng).into()
+            };
+
+            entry.set_password(&STANDARD.encode(key))
+                .map_err(|e| format!("Failed to store master key in the system keychain: {}", e))?;
+            // 确认钥匙串里的密钥可以读回之后才删除旧文件，否则已有的密文将无法解密
+            if decode_key(&entry.get_password().map_err(|e| format!("Failed to read back master key: {}", e))?)? != key {
+                return Err("Master key stored in the system keychain does not match".to_string());
+            }
+            if legacy_path.exists() {
+                fs::remove_file(&legacy_path)
+                    .map_err(|e| format!("Failed to remove legacy master key file: {}", e))?;
+            }
+            key
+        }
+        Err(e) => return Err(format!("Failed to read master key from the system keychain: {}", e)),
+    };
+
+    let _ = MASTER_KEY.set(key);
+    Ok(())
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    let bytes = STANDARD.decode(encoded.trim())
+        .map_err(|e| format!("Master key is corrupted: {}", e))?;
+    <[u8; 32]>::try_from(bytes.as_slice())
+        .map_err(|_| "Master key has an invalid length".to_string())
+}
+
+fn master_key() -> Result<&'static [u8; 32], String> {
+    MASTER_KEY.get().ok_or_else(|| "Master key has not been initialized".to_string())
+}
+
+/// Masks a plaintext secret for display, keeping the first and last four characters.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let start: String = chars[..4].iter().collect();
+    let end: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}{}", start, "*".repeat((chars.len() - 8).min(20)), end)
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypts a secret for storage. Empty and already-encrypted values are returned unchanged.
+pub fn encrypt_secret(plaintext: &str) -> Result<String, String> {
+    if plaintext.is_empty() || is_encrypted(plaintext) {
+        return Ok(plaintext.to_string());
+    }
+    encrypt_with(master_key()?, plaintext)
+}
+
+/// Decrypts a stored secret. Values without the encryption prefix are treated as plaintext.
+pub fn decrypt_secret(stored: &str) -> Result<String, String> {
+    if !is_encrypted(stored) {
+        return Ok(stored.to_string());
+    }
+    decrypt_with(master_key()?, stored)
+}
+
+fn encrypt_with(key: &[u8; 32], plaintext: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+fn decrypt_with(key: &[u8; 32], stored: &str) -> Result<String, String> {
+    let encoded = stored.strip_prefix(ENCRYPTED_PREFIX).unwrap_or(stored);
+    let payload = STANDARD.decode(encoded)
+        .map_err(|e| format!("Encrypted secret is corrupted: {}", e))?;
+    if payload.len() <= NONCE_LEN {
+        return Err("Encrypted secret is truncated".to_string());
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret: wrong master key or tampered data".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("Decrypted secret is not valid UTF-8: {}", e))
+}
+
+/// Encrypts any secrets that were stored in plaintext before encryption at rest was introduced.
+pub fn encrypt_plaintext_rows(conn: &Connection) -> Result<(), String> {
+    for (table, column) in [("api_keys", "ANTHROPIC_API_KEY"), ("providers", "api_key")] {
+        let rows: Vec<(String, String)> = {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, {column} FROM {table} WHERE {column} != '' AND {column} NOT LIKE '{ENCRYPTED_PREFIX}%'"
+            )).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+
+        for (id, plaintext) in rows {
+            let encrypted = encrypt_secret(&plaintext)?;
+            conn.execute(
+                &format!("UPDATE {table} SET {column} = ?1 WHERE id = ?2"),
+                (&encrypted, &id),
+            ).map_err(|e| format!("Failed to encrypt {}.{}: {}", table, column, e))?;
+        }
+    }
+
+    // Router 的全局 APIKEY 存在 router_configs 里
+    let router_api_key: Option<String> = conn.query_row(
+        &format!("SELECT config_value FROM router_configs WHERE config_key = 'anthropic_api_key' AND config_value != '' AND config_value NOT LIKE '{ENCRYPTED_PREFIX}%'"),
+        [],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+    if let Some(plaintext) = router_api_key {
+        conn.execute(
+            "UPDATE router_configs SET config_value = ?1 WHERE config_key = 'anthropic_api_key'",
+            [encrypt_secret(&plaintext)?],
+        ).map_err(|e| format!("Failed to encrypt the router APIKEY: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Installs a fixed master key so tests can encrypt without a system keychain.
+#[cfg(test)]
+pub fn init_test_master_key() {
+    let _ = MASTER_KEY.set([7; 32]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [1; 32];
+    const OTHER_KEY: [u8; 32] = [2; 32];
+
+    #[test]
+    fn round_trips_a_secret() {
+        let encrypted = encrypt_with(&KEY, "sk-ant-api03-secret").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("sk-ant-api03-secret"));
+        assert_eq!(decrypt_with(&KEY, &encrypted).unwrap(), "sk-ant-api03-secret");
+    }
+
+    #[test]
+    fn uses_a_fresh_nonce_for_every_encryption() {
+        let first = encrypt_with(&KEY, "same").unwrap();
+        let second = encrypt_with(&KEY, "same").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(decrypt_with(&KEY, &second).unwrap(), "same");
+    }
+
+    #[test]
+    fn rejects_the_wrong_key() {
+        let encrypted = encrypt_with(&KEY, "sk-ant-api03-secret").unwrap();
+        let error = decrypt_with(&OTHER_KEY, &encrypted).unwrap_err();
+        assert!(error.contains("wrong master key"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_tampered_and_truncated_ciphertext() {
+        let encrypted = encrypt_with(&KEY, "sk-ant-api03-secret").unwrap();
+        let mut payload = STANDARD.decode(&encrypted[ENCRYPTED_PREFIX.len()..]).unwrap();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+        let tampered = format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(&payload));
+        assert!(decrypt_with(&KEY, &tampered).is_err());
+
+        let truncated = format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode([0u8; NONCE_LEN]));
+        assert_eq!(decrypt_with(&KEY, &truncated).unwrap_err(), "Encrypted secret is truncated");
+    }
+
+    #[test]
+    fn passes_through_plaintext_and_empty_values() {
+        init_test_master_key();
+        assert_eq!(decrypt_secret("legacy-plaintext").unwrap(), "legacy-plaintext");
+        assert_eq!(encrypt_secret("").unwrap(), "");
+
+        let encrypted = encrypt_secret("sk-ant-api03-secret").unwrap();
+        assert_eq!(encrypt_secret(&encrypted).unwrap(), encrypted);
+        assert_eq!(decrypt_secret(&encrypted).unwrap(), "sk-ant-api03-secret");
+    }
+
+    #[test]
+    fn masks_secrets() {
+        assert_eq!(mask_secret("short"), "*****");
+        assert_eq!(mask_secret("sk-ant-api03-abcdefgh"), "sk-a*************efgh");
+    }
+}
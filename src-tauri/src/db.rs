@@ -1,16 +1,211 @@
-use rusqlite::{Connection, Result, OptionalExtension};
+use rusqlite::{Connection, Transaction};
+use std::cell::Cell;
+use std::fmt;
 use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 use tauri::Manager;
+use crate::crypto;
 
-pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
-    let api_dir = app.path().app_data_dir().unwrap().join("api_keys");
-    fs::create_dir_all(&api_dir).map_err(|_e| rusqlite::Error::InvalidColumnType(0, "Failed to create directory".to_string(), rusqlite::types::Type::Null))?;
-    
-    let db_path = api_dir.join("claude_keys.db");
-    let conn = Connection::open(db_path)?;
-    
-    // Create the api_keys table if it doesn't exist
+type Result<T, E = DbError> = std::result::Result<T, E>;
+
+/// Why opening or using the database failed.
+#[derive(Debug)]
+pub enum DbError {
+    Sqlite(rusqlite::Error),
+    Io(std::io::Error),
+    /// Loading the master key or encrypting stored secrets failed.
+    Crypto(String),
+    Migration { version: i64, description: &'static str, source: Box<DbError> },
+    LockPoisoned,
+    /// The connection was requested again while this thread already holds it.
+    AlreadyBorrowed,
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlite(e) => write!(f, "{}", e),
+            DbError::Io(e) => write!(f, "{}", e),
+            DbError::Crypto(message) => write!(f, "{}", message),
+            DbError::Migration { version, description, source } => {
+                write!(f, "Migration {} ({}) failed and was rolled back: {}", version, description, source)
+            }
+            DbError::LockPoisoned => write!(f, "Database connection lock is poisoned"),
+            DbError::AlreadyBorrowed => write!(f, "Database connection is already borrowed on this thread"),
+        }
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DbError::Sqlite(e) => Some(e),
+            DbError::Io(e) => Some(e),
+            DbError::Migration { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(e: rusqlite::Error) -> Self {
+        DbError::Sqlite(e)
+    }
+}
+
+/// A single schema change. Migrations are applied in `version` order, each inside its own
+/// transaction, and recorded in `schema_version` so they never run twice.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    up: fn(&Transaction) -> Result<()>,
+}
+
+// 新的迁移只能追加到末尾，已发布的版本号不能修改
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "create api_keys table", up: create_api_keys_table },
+    Migration { version: 2, description: "rename api_keys.key to ANTHROPIC_API_KEY", up: rename_key_column },
+    Migration { version: 3, description: "rename lowercase api_keys env columns to uppercase", up: rename_lowercase_columns },
+    Migration { version: 4, description: "rename api_keys.ANTHROPIC_AUTH_TOKEN to ANTHROPIC_API_KEY", up: rename_auth_token_column },
+    Migration { version: 5, description: "drop api_keys.CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC", up: drop_disable_traffic_column },
+    Migration { version: 6, description: "add api_keys.is_active", up: add_is_active_column },
+    Migration { version: 7, description: "create config, backup, router and project tables", up: create_base_tables },
+    Migration { version: 8, description: "encrypt plaintext secrets", up: encrypt_plaintext_secrets },
+    Migration { version: 9, description: "add providers.extra", up: add_provider_extra_column },
+    Migration { version: 10, description: "create router_profiles table", up: create_router_profiles_table },
+    Migration { version: 11, description: "add providers.api_key_id", up: add_provider_api_key_id_column },
+    Migration { version: 12, description: "create api_key_history table", up: create_api_key_history_table },
+    Migration { version: 13, description: "add api_keys expiry, tags, owner and last_used_at", up: add_api_key_metadata_columns },
+    Migration { version: 14, description: "add providers.position", up: add_provider_position_column },
+    // encrypt_plaintext_rows 后来也覆盖了 router_configs 里的 APIKEY，重新执行一遍即可
+    Migration { version: 15, description: "encrypt the router APIKEY", up: encrypt_plaintext_secrets },
+];
+
+fn has_column(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    Ok(tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        (table, column),
+        |row| row.get(0),
+    )?)
+}
+
+/// The single SQLite connection shared by every command, held in Tauri managed state.
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    /// Opens the database, configures it for concurrent access and applies pending migrations.
+    pub fn open(app: &tauri::AppHandle) -> Result<Self> {
+        let api_dir = app.path().app_data_dir().unwrap().join("api_keys");
+        fs::create_dir_all(&api_dir).map_err(DbError::Io)?;
+
+        // The encryption migration needs the master key, so load it before migrating
+        crypto::init_master_key(&api_dir).map_err(DbError::Crypto)?;
+
+        let db_path = api_dir.join("claude_keys.db");
+        let mut conn = Connection::open(db_path)?;
+
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+
+        run_migrations(&mut conn)?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+}
+
+thread_local! {
+    static CONNECTION_BORROWED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The locked shared connection.
+///
+/// The mutex is not reentrant, so only command bodies lock it; helpers take the `&Connection`
+/// from their caller. Locking twice on one thread returns `DbError::AlreadyBorrowed` instead of
+/// deadlocking. The guard is `!Send`, so it can never be held across an `.await`.
+pub struct DbConnection<'a> {
+    guard: MutexGuard<'a, Connection>,
+}
+
+impl Deref for DbConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.guard
+    }
+}
+
+impl DerefMut for DbConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.guard
+    }
+}
+
+impl Drop for DbConnection<'_> {
+    fn drop(&mut self) {
+        CONNECTION_BORROWED.with(|borrowed| borrowed.set(false));
+    }
+}
+
+/// Borrows the shared connection for the rest of the current synchronous section.
+pub fn get_database_connection(app: &tauri::AppHandle) -> Result<DbConnection<'_>> {
+    if CONNECTION_BORROWED.with(|borrowed| borrowed.get()) {
+        return Err(DbError::AlreadyBorrowed);
+    }
+
+    let guard = app.state::<Database>()
+        .inner()
+        .conn
+        .lock()
+        .map_err(|_| DbError::LockPoisoned)?;
+    CONNECTION_BORROWED.with(|borrowed| borrowed.set(true));
+    Ok(DbConnection { guard })
+}
+
+fn run_migrations(conn: &mut Connection) -> Result<()> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+fn apply_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<()> {
     conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        (),
+    )?;
+
+    let current_version: i64 = conn.query_row(
+        "SELECT MAX(version) FROM schema_version",
+        [],
+        |row| row.get::<_, Option<i64>>(0),
+    )?.unwrap_or(0);
+
+    for migration in migrations.iter().filter(|m| m.version > current_version) {
+        let tx = conn.transaction()?;
+
+        (migration.up)(&tx).map_err(|e| DbError::Migration {
+            version: migration.version,
+            description: migration.description,
+            source: Box::new(e),
+        })?;
+
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            (migration.version, migration.description, chrono::Utc::now().to_rfc3339()),
+        )?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+fn create_api_keys_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
         "CREATE TABLE IF NOT EXISTS api_keys (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
@@ -23,94 +218,52 @@ pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
         )",
         (),
     )?;
+    Ok(())
+}
 
-    // Check if we need to migrate from old schema (key column to ANTHROPIC_API_KEY)
-    let has_key_column: Result<bool, rusqlite::Error> = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('api_keys') WHERE name = 'key'",
-        [],
-        |row| row.get(0),
-    );
+// The column migrations below probe the schema because databases created before the
+// migration framework existed may be at any point of the old ad-hoc upgrade chain.
 
-    if let Ok(true) = has_key_column {
-        // Migration needed: rename key column to ANTHROPIC_API_KEY
-        conn.execute(
-            "ALTER TABLE api_keys RENAME COLUMN key TO ANTHROPIC_API_KEY",
-            (),
-        ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to migrate database schema: {}", e), rusqlite::types::Type::Null))?;
+fn rename_key_column(tx: &Transaction) -> Result<()> {
+    if has_column(tx, "api_keys", "key")? {
+        tx.execute("ALTER TABLE api_keys RENAME COLUMN key TO ANTHROPIC_API_KEY", ())?;
     }
+    Ok(())
+}
 
-    // Check if we need to migrate from lowercase to uppercase column names
-    let has_lowercase_columns: Result<i64, rusqlite::Error> = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('api_keys') WHERE name IN ('anthropic_api_key', 'anthropic_base_url')",
-        [],
-        |row| row.get(0),
-    );
-
-    if let Ok(count) = has_lowercase_columns {
-        if count > 0 {
-            // Migration needed: rename lowercase columns to uppercase
-            if count >= 1 {
-                conn.execute(
-                    "ALTER TABLE api_keys RENAME COLUMN anthropic_api_key TO ANTHROPIC_API_KEY",
-                    (),
-                ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to migrate database schema: {}", e), rusqlite::types::Type::Null))?;
-            }
-            if count >= 2 {
-                conn.execute(
-                    "ALTER TABLE api_keys RENAME COLUMN anthropic_base_url TO ANTHROPIC_BASE_URL",
-                    (),
-                ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to migrate database schema: {}", e), rusqlite::types::Type::Null))?;
-            }
-        }
+fn rename_lowercase_columns(tx: &Transaction) -> Result<()> {
+    if has_column(tx, "api_keys", "anthropic_api_key")? {
+        tx.execute("ALTER TABLE api_keys RENAME COLUMN anthropic_api_key TO ANTHROPIC_API_KEY", ())?;
     }
-
-    // Check if we need to migrate from ANTHROPIC_AUTH_TOKEN to ANTHROPIC_API_KEY
-    let has_auth_token_column: Result<bool, rusqlite::Error> = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('api_keys') WHERE name = 'ANTHROPIC_AUTH_TOKEN'",
-        [],
-        |row| row.get(0),
-    );
-
-    if let Ok(true) = has_auth_token_column {
-        // Migration needed: rename ANTHROPIC_AUTH_TOKEN column to ANTHROPIC_API_KEY
-        conn.execute(
-            "ALTER TABLE api_keys RENAME COLUMN ANTHROPIC_AUTH_TOKEN TO ANTHROPIC_API_KEY",
-            (),
-        ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to migrate database schema from ANTHROPIC_AUTH_TOKEN to ANTHROPIC_API_KEY: {}", e), rusqlite::types::Type::Null))?;
+    if has_column(tx, "api_keys", "anthropic_base_url")? {
+        tx.execute("ALTER TABLE api_keys RENAME COLUMN anthropic_base_url TO ANTHROPIC_BASE_URL", ())?;
     }
+    Ok(())
+}
 
-    // Check if CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC column exists and remove it
-    let has_disable_traffic_column: Result<bool, rusqlite::Error> = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('api_keys') WHERE name = 'CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC'",
-        [],
-        |row| row.get(0),
-    );
-
-    if let Ok(true) = has_disable_traffic_column {
-        // Remove the CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC column
-        conn.execute(
-            "ALTER TABLE api_keys DROP COLUMN CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC",
-            (),
-        ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to migrate database schema: {}", e), rusqlite::types::Type::Null))?;
+fn rename_auth_token_column(tx: &Transaction) -> Result<()> {
+    if has_column(tx, "api_keys", "ANTHROPIC_AUTH_TOKEN")? {
+        tx.execute("ALTER TABLE api_keys RENAME COLUMN ANTHROPIC_AUTH_TOKEN TO ANTHROPIC_API_KEY", ())?;
     }
+    Ok(())
+}
 
-    // Check if is_active column exists, add it if it doesn't
-    let has_is_active_column: Result<bool, rusqlite::Error> = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('api_keys') WHERE name = 'is_active'",
-        [],
-        |row| row.get(0),
-    );
+fn drop_disable_traffic_column(tx: &Transaction) -> Result<()> {
+    if has_column(tx, "api_keys", "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC")? {
+        tx.execute("ALTER TABLE api_keys DROP COLUMN CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC", ())?;
+    }
+    Ok(())
+}
 
-    if let Ok(false) = has_is_active_column {
-        // Add the is_active column with default value 1 (true)
-        conn.execute(
-            "ALTER TABLE api_keys ADD COLUMN is_active INTEGER NOT NULL DEFAULT 1",
-            (),
-        ).map_err(|e| rusqlite::Error::InvalidColumnType(0, format!("Failed to add is_active column: {}", e), rusqlite::types::Type::Null))?;
+fn add_is_active_column(tx: &Transaction) -> Result<()> {
+    if !has_column(tx, "api_keys", "is_active")? {
+        tx.execute("ALTER TABLE api_keys ADD COLUMN is_active INTEGER NOT NULL DEFAULT 1", ())?;
     }
+    Ok(())
+}
 
-    // Create the config_paths table if it doesn't exist
-    conn.execute(
+fn create_base_tables(tx: &Transaction) -> Result<()> {
+    Ok(tx.execute_batch(
         "CREATE TABLE IF NOT EXISTS config_paths (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
@@ -118,45 +271,29 @@ pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
             description TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the current_config_path table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS current_config_path (
+        CREATE TABLE IF NOT EXISTS current_config_path (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             path TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the current_router_config_path table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS current_router_config_path (
+        CREATE TABLE IF NOT EXISTS current_router_config_path (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             path TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the backups table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS backups (
+        CREATE TABLE IF NOT EXISTS backups (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             filename TEXT NOT NULL,
             content TEXT NOT NULL,
             size INTEGER NOT NULL,
             created_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the route_configs table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS route_configs (
+        CREATE TABLE IF NOT EXISTS route_configs (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
             path TEXT NOT NULL,
@@ -167,13 +304,9 @@ pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
             description TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the providers table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS providers (
+        CREATE TABLE IF NOT EXISTS providers (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL UNIQUE,
             api_base_url TEXT NOT NULL,
@@ -182,25 +315,17 @@ pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
             transformer TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the router_configs table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS router_configs (
+        CREATE TABLE IF NOT EXISTS router_configs (
             id TEXT PRIMARY KEY,
             config_key TEXT NOT NULL UNIQUE,
             config_value TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the projects table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS projects (
+        CREATE TABLE IF NOT EXISTS projects (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL,
             path TEXT NOT NULL UNIQUE,
@@ -211,20 +336,208 @@ pub fn get_database_connection(app: &tauri::AppHandle) -> Result<Connection> {
             scan_time TEXT NOT NULL,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
-        )",
-        (),
-    )?;
+        );
 
-    // Create the project_categories table if it doesn't exist
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS project_categories (
+        CREATE TABLE IF NOT EXISTS project_categories (
             id TEXT PRIMARY KEY,
             name TEXT NOT NULL UNIQUE,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
+        );",
+    )?)
+}
+
+fn encrypt_plaintext_secrets(tx: &Transaction) -> Result<()> {
+    crypto::encrypt_plaintext_rows(tx).map_err(DbError::Crypto)
+}
+
+fn add_provider_extra_column(tx: &Transaction) -> Result<()> {
+    if !has_column(tx, "providers", "extra")? {
+        tx.execute("ALTER TABLE providers ADD COLUMN extra TEXT", ())?;
+    }
+    Ok(())
+}
+
+fn create_router_profiles_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS router_profiles (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            config TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
         )",
         (),
     )?;
-    
-    Ok(conn)
+    Ok(())
+}
+
+fn add_provider_api_key_id_column(tx: &Transaction) -> Result<()> {
+    if !has_column(tx, "providers", "api_key_id")? {
+        tx.execute("ALTER TABLE providers ADD COLUMN api_key_id TEXT", ())?;
+    }
+    Ok(())
+}
+
+fn create_api_key_history_table(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS api_key_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            api_key_id TEXT NOT NULL,
+            masked_key TEXT NOT NULL,
+            encrypted_key TEXT,
+            reason TEXT NOT NULL,
+            rotated_at TEXT NOT NULL,
+            reverted_at TEXT
+        )",
+        (),
+    )?;
+    Ok(())
+}
+
+fn add_api_key_metadata_columns(tx: &Transaction) -> Result<()> {
+    for (column, definition) in [
+        ("expires_at", "TEXT"),
+        ("tags", "TEXT NOT NULL DEFAULT '[]'"),
+        ("owner", "TEXT"),
+        ("last_used_at", "TEXT"),
+    ] {
+        if !has_column(tx, "api_keys", column)? {
+            tx.execute(&format!("ALTER TABLE api_keys ADD COLUMN {} {}", column, definition), ())?;
+        }
+    }
+    Ok(())
+}
+
+// 之前按 created_at 排序，同一次保存的提供商时间相同，顺序不稳定；按插入顺序回填
+fn add_provider_position_column(tx: &Transaction) -> Result<()> {
+    if !has_column(tx, "providers", "position")? {
+        tx.execute("ALTER TABLE providers ADD COLUMN position INTEGER NOT NULL DEFAULT 0", ())?;
+        tx.execute(
+            "UPDATE providers SET position = (SELECT COUNT(*) FROM providers AS earlier WHERE earlier.rowid < providers.rowid)",
+            (),
+        )?;
+    }
+    Ok(())
+}
+
+/// An in-memory database with every migration applied.
+#[cfg(test)]
+pub fn open_test_connection() -> Connection {
+    crypto::init_test_master_key();
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied_versions(conn: &Connection) -> Vec<i64> {
+        let mut stmt = conn.prepare("SELECT version FROM schema_version ORDER BY version").unwrap();
+        let versions = stmt.query_map([], |row| row.get(0)).unwrap();
+        versions.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)").unwrap();
+        let names = stmt.query_map([table], |row| row.get(0)).unwrap();
+        names.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn migration_versions_are_unique_and_ascending() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[test]
+    fn applies_every_migration_once() {
+        let mut conn = open_test_connection();
+        let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(applied_versions(&conn), expected);
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(applied_versions(&conn), expected);
+    }
+
+    #[test]
+    fn upgrades_a_legacy_api_keys_table() {
+        crypto::init_test_master_key();
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE api_keys (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key TEXT NOT NULL,
+                description TEXT,
+                anthropic_base_url TEXT,
+                CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC INTEGER,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            INSERT INTO api_keys VALUES ('k1', 'old', 'sk-ant-legacy', NULL, 'https://relay.example', 1, 'now', 'now');",
+        ).unwrap();
+
+        run_migrations(&mut conn).unwrap();
+
+        let columns = columns(&conn, "api_keys");
+        for expected in ["ANTHROPIC_API_KEY", "ANTHROPIC_BASE_URL", "is_active", "expires_at", "tags", "owner", "last_used_at"] {
+            assert!(columns.iter().any(|c| c == expected), "missing column {}", expected);
+        }
+        assert!(!columns.iter().any(|c| c == "key" || c == "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC"));
+
+        let (stored, base_url, is_active): (String, String, bool) = conn.query_row(
+            "SELECT ANTHROPIC_API_KEY, ANTHROPIC_BASE_URL, is_active FROM api_keys WHERE id = 'k1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert!(crypto::is_encrypted(&stored));
+        assert_eq!(crypto::decrypt_secret(&stored).unwrap(), "sk-ant-legacy");
+        assert_eq!(base_url, "https://relay.example");
+        assert!(is_active);
+    }
+
+    fn create_first(tx: &Transaction) -> Result<()> {
+        tx.execute("CREATE TABLE first (id INTEGER)", ())?;
+        Ok(())
+    }
+
+    fn create_second_then_fail(tx: &Transaction) -> Result<()> {
+        tx.execute("CREATE TABLE second (id INTEGER)", ())?;
+        tx.execute("INSERT INTO missing_table VALUES (1)", ())?;
+        Ok(())
+    }
+
+    #[test]
+    fn rolls_back_a_failed_migration_and_keeps_earlier_ones() {
+        let migrations = [
+            Migration { version: 1, description: "create first", up: create_first },
+            Migration { version: 2, description: "create second", up: create_second_then_fail },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let error = apply_migrations(&mut conn, &migrations).unwrap_err();
+        assert!(matches!(error, DbError::Migration { version: 2, .. }), "{}", error);
+
+        assert_eq!(applied_versions(&conn), vec![1]);
+        assert_eq!(columns(&conn, "first"), vec!["id".to_string()]);
+        assert!(columns(&conn, "second").is_empty());
+    }
+
+    #[test]
+    fn resumes_from_the_recorded_version() {
+        let migrations = [
+            Migration { version: 1, description: "create first", up: create_first },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply_migrations(&mut conn, &migrations).unwrap();
+
+        // 已记录的版本不会再执行，否则 CREATE TABLE 会因为表已存在而失败
+        apply_migrations(&mut conn, &migrations).unwrap();
+        assert_eq!(applied_versions(&conn), vec![1]);
+    }
 }
@@ -1,6 +1,7 @@
 #![allow(unused_imports)]
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod db;
+mod crypto;
 mod models;
 mod commands;
 use tauri::Manager;
@@ -14,6 +15,20 @@ use crate::commands::router;
 use crate::commands::route_config;
 use crate::commands::utils;
 use crate::commands::ide;
+use crate::commands::connectivity;
+use crate::commands::provider_health;
+use crate::commands::router_validation;
+use crate::commands::router_profiles;
+use crate::commands::router_process::{self, RouterProcess};
+use crate::commands::router_logs;
+use crate::commands::transformers;
+use crate::commands::provider_presets;
+use crate::commands::permissions;
+use crate::commands::permission_simulator;
+use crate::commands::settings_layers;
+use crate::commands::project_settings;
+use crate::commands::settings_schema;
+use crate::commands::watcher::{self, ConfigWatcher};
 use crate::commands::category::{self, CustomCategoryStore};
 
 
@@ -24,12 +39,26 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .manage(CustomCategoryStore::default()) // Add this line
+        .manage(ConfigWatcher::default())
+        .manage(RouterProcess::default())
         .setup(|app| {
+            // 打开数据库并执行迁移，所有命令共享这一个连接
+            let database = db::Database::open(app.handle())?;
+            app.manage(database);
+
             // 初始化CustomCategoryStore
             let store = app.state::<CustomCategoryStore>();
             if let Err(e) = store.inner().init(&app.handle()) {
                 eprintln!("Failed to initialize CustomCategoryStore: {}", e);
             }
+
+            // 监听配置文件的外部修改
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = watcher::restart_config_watcher(&handle).await {
+                    eprintln!("Failed to start config watcher: {}", e);
+                }
+            });
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -40,6 +69,14 @@ pub fn run() {
             api_keys::update_api_key,
             api_keys::delete_api_key,
             api_keys::toggle_api_key_active,
+            api_keys::reveal_api_key,
+            api_keys::activate_api_key,
+            api_keys::detect_api_key_drift,
+            api_keys::rotate_api_key,
+            api_keys::revert_api_key,
+            api_keys::get_api_key_history,
+            api_keys::get_expiring_api_keys,
+            connectivity::test_api_key,
             route_config::create_route_config,
             route_config::get_route_configs,
             route_config::get_route_configs_config,
@@ -63,8 +100,10 @@ pub fn run() {
             config_path::delete_config_path,
             config_path::save_config_path,
             config_path::get_config_path,
+            watcher::refresh_config_watcher,
             config::update_config_env,
             router::get_router_config,
+            router::reveal_router_provider_key,
             router::update_router_config,
             router::create_router_config,
             router::get_raw_router_config,
@@ -76,6 +115,47 @@ pub fn run() {
             router::delete_router_backup,
             router::select_router_config_path,
             router::save_raw_router_config,
+            provider_health::check_router_providers,
+            provider_health::sync_provider_models,
+            router_validation::validate_router_config,
+            router_validation::validate_raw_router_config,
+            router_profiles::get_router_profiles,
+            router_profiles::create_router_profile,
+            router_profiles::update_router_profile,
+            router_profiles::delete_router_profile,
+            router_profiles::activate_router_profile,
+            router_process::start_router_process,
+            router_process::stop_router_process,
+            router_process::restart_router_process,
+            router_process::get_router_process_status,
+            router_process::get_router_process_logs,
+            router_process::get_router_process_options,
+            router_process::set_router_process_options,
+            router_logs::list_router_log_files,
+            router_logs::tail_router_log,
+            transformers::get_custom_transformers,
+            transformers::add_custom_transformer,
+            transformers::update_custom_transformer,
+            transformers::remove_custom_transformer,
+            transformers::add_provider_transformer,
+            transformers::remove_provider_transformer,
+            transformers::reorder_provider_transformers,
+            provider_presets::get_provider_presets,
+            provider_presets::create_provider_from_preset,
+            permissions::get_permissions,
+            permissions::validate_permission_rule,
+            permissions::add_permission_rule,
+            permissions::remove_permission_rule,
+            permissions::reorder_permission_rule,
+            permissions::set_permission_default_mode,
+            permission_simulator::simulate_permission,
+            settings_layers::get_effective_settings,
+            project_settings::get_project_settings,
+            project_settings::create_project_settings,
+            project_settings::save_project_settings,
+            project_settings::apply_api_key_to_category,
+            project_settings::apply_permission_preset_to_category,
+            settings_schema::validate_claude_settings,
             utils::check_feature_status,
             utils::install_feature,
             project::scan_and_save_projects,
@@ -26,63 +26,178 @@ pub struct ScanOptions {
 }
 
 // Claude Code Router 相关数据结构
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// 字段名与 CCR 官方的 config.json 保持一致；alias 用于兼容本应用早期写出的 snake_case 格式，
+// extra 收集未识别的字段，保证读写后不会丢失
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Transformer {
-    #[serde(rename = "use")]
+    #[serde(rename = "use", default, skip_serializing_if = "Vec::is_empty")]
     pub use_transformers: Vec<serde_json::Value>,
+    /// Per-model transformer chains (`"model": { "use": [...] }`) and any unknown keys.
     #[serde(flatten)]
-    pub model_specific: HashMap<String, HashMap<String, Vec<serde_json::Value>>>,
+    pub model_specific: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Transformer {
+    /// Converts the legacy `{ "name": ..., "args": ... }` shape into a `use` chain.
+    pub fn normalize_legacy(&mut self) {
+        if !self.use_transformers.is_empty() {
+            return;
+        }
+        let Some(serde_json::Value::String(name)) = self.model_specific.remove("name") else {
+            return;
+        };
+        let entry = match self.model_specific.remove("args") {
+            Some(args) if !args.is_null() => serde_json::json!([name, args]),
+            _ => serde_json::Value::String(name),
+        };
+        self.use_transformers.push(entry);
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Provider {
     pub name: String,
     pub api_base_url: String,
+    /// A literal key, or a `$VAR` reference that CCR expands from the environment.
+    #[serde(default)]
     pub api_key: String,
+    /// Id of a stored `ApiKey` to use instead of `api_key`. Resolved when `config.json`
+    /// is written and never exported itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<String>,
+    #[serde(default)]
     pub models: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transformer: Option<Transformer>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RouterConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub think: Option<String>,
-    #[serde(rename = "longContext")]
+    #[serde(rename = "longContext", alias = "long_context", skip_serializing_if = "Option::is_none")]
     pub long_context: Option<String>,
-    #[serde(rename = "longContextThreshold")]
+    #[serde(rename = "longContextThreshold", alias = "long_context_threshold", skip_serializing_if = "Option::is_none")]
     pub long_context_threshold: Option<u32>,
-    #[serde(rename = "webSearch")]
+    #[serde(rename = "webSearch", alias = "web_search", skip_serializing_if = "Option::is_none")]
     pub web_search: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+/// An entry of the top-level `transformers` list. Upstream entries point at a plugin file;
+/// older versions of this app wrote `{ "name": ..., "args": ... }` entries without a path,
+/// whose `args` are kept as-is in `extra`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomTransformer {
-    pub path: String,
+    /// The name provider chains use to reference the plugin. CCR itself reads the name from
+    /// the plugin class and ignores this key, so it is only present on entries added here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<HashMap<String, serde_json::Value>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ClaudeCodeRouterConfig {
-    #[serde(rename = "ANTHROPIC_API_KEY", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "APIKEY", alias = "ANTHROPIC_API_KEY", alias = "anthropic_api_key", skip_serializing_if = "Option::is_none")]
     pub anthropic_api_key: Option<String>,
-    #[serde(rename = "PROXY_URL", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "PROXY_URL", alias = "proxy_url", skip_serializing_if = "Option::is_none")]
     pub proxy_url: Option<String>,
-    #[serde(rename = "LOG", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "LOG", alias = "log", skip_serializing_if = "Option::is_none")]
     pub log: Option<bool>,
-    #[serde(rename = "HOST", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "HOST", alias = "host", skip_serializing_if = "Option::is_none")]
     pub host: Option<String>,
-    #[serde(rename = "NON_INTERACTIVE_MODE", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "NON_INTERACTIVE_MODE", alias = "non_interactive_mode", skip_serializing_if = "Option::is_none")]
     pub non_interactive_mode: Option<bool>,
-    #[serde(rename = "API_TIMEOUT_MS", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "API_TIMEOUT_MS", alias = "api_timeout_ms", skip_serializing_if = "Option::is_none")]
     pub api_timeout_ms: Option<u32>,
-    #[serde(rename = "CUSTOM_ROUTER_PATH", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "CUSTOM_ROUTER_PATH", alias = "custom_router_path", skip_serializing_if = "Option::is_none")]
     pub custom_router_path: Option<String>,
-    #[serde(rename = "Providers")]
+    #[serde(rename = "Providers", alias = "providers", default)]
     pub providers: Vec<Provider>,
-    #[serde(rename = "Router")]
+    #[serde(rename = "Router", alias = "router", default)]
     pub router: RouterConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub transformers: Option<Vec<CustomTransformer>>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ClaudeCodeRouterConfig {
+    /// Parses a `config.json` in either the upstream CCR shape or one of the legacy shapes
+    /// this app used to write.
+    pub fn from_json_str(content: &str) -> Result<Self, serde_json::Error> {
+        let mut config: Self = serde_json::from_str(content)?;
+        config.normalize_legacy();
+        Ok(config)
+    }
+
+    pub fn normalize_legacy(&mut self) {
+        for provider in &mut self.providers {
+            if let Some(transformer) = provider.transformer.as_mut() {
+                transformer.normalize_legacy();
+            }
+        }
+    }
+}
+
+/// A provider template from the presets catalog.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderPreset {
+    pub id: String,
+    /// Default provider name used when instantiating the preset.
+    pub name: String,
+    pub description: Option<String>,
+    pub api_base_url: String,
+    #[serde(default)]
+    pub models: Vec<String>,
+    pub transformer: Option<Transformer>,
+    /// Key to use when none is given, for local providers that ignore it.
+    pub default_api_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderPresetCatalog {
+    pub version: u32,
+    pub presets: Vec<ProviderPreset>,
+}
+
+/// A named, stored router setup that can be written to `config.json` on demand.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RouterProfile {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub config: ClaudeCodeRouterConfig,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRouterProfileRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Defaults to a snapshot of the current router config.
+    pub config: Option<ClaudeCodeRouterConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRouterProfileRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub config: Option<ClaudeCodeRouterConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -95,6 +210,12 @@ pub struct ApiKey {
     #[serde(rename = "ANTHROPIC_BASE_URL")]
     pub anthropic_base_url: Option<String>,
     pub is_active: bool,
+    pub expires_at: Option<String>,
+    /// Free-form labels such as `personal`, `team` or `client`.
+    pub tags: Vec<String>,
+    pub owner: Option<String>,
+    /// Last time the key was written into a Claude settings file.
+    pub last_used_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -153,6 +274,9 @@ pub struct CreateApiKeyRequest {
     pub description: Option<String>,
     #[serde(rename = "ANTHROPIC_BASE_URL")]
     pub anthropic_base_url: Option<String>,
+    pub expires_at: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub owner: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -163,23 +287,88 @@ pub struct UpdateApiKeyRequest {
     pub description: Option<String>,
     #[serde(rename = "ANTHROPIC_BASE_URL")]
     pub anthropic_base_url: Option<String>,
+    pub expires_at: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub owner: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A Claude Code `settings.json`. Keys the app does not model are kept in `extra`, so reading
+/// and writing a file through this type never drops anything.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ClaudeSettings {
-    #[serde(rename = "ANTHROPIC_API_KEY")]
-    pub anthropic_api_key: Option<String>,
-    #[serde(rename = "ANTHROPIC_BASE_URL")]
-    pub anthropic_base_url: Option<String>,
+    #[serde(rename = "apiKeyHelper", default, skip_serializing_if = "Option::is_none")]
+    pub api_key_helper: Option<String>,
+    #[serde(rename = "awsAuthRefresh", default, skip_serializing_if = "Option::is_none")]
+    pub aws_auth_refresh: Option<String>,
+    #[serde(rename = "awsCredentialExport", default, skip_serializing_if = "Option::is_none")]
+    pub aws_credential_export: Option<String>,
+    #[serde(rename = "cleanupPeriodDays", default, skip_serializing_if = "Option::is_none")]
+    pub cleanup_period_days: Option<u32>,
+    #[serde(rename = "disableAllHooks", default, skip_serializing_if = "Option::is_none")]
+    pub disable_all_hooks: Option<bool>,
+    #[serde(rename = "disabledMcpjsonServers", default, skip_serializing_if = "Option::is_none")]
+    pub disabled_mcpjson_servers: Option<Vec<String>>,
+    #[serde(rename = "enableAllProjectMcpServers", default, skip_serializing_if = "Option::is_none")]
+    pub enable_all_project_mcp_servers: Option<bool>,
+    #[serde(rename = "enabledMcpjsonServers", default, skip_serializing_if = "Option::is_none")]
+    pub enabled_mcpjson_servers: Option<Vec<String>>,
+    /// Values are usually strings, but numbers such as `CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC: 1` occur too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<serde_json::Map<String, serde_json::Value>>,
+    /// `claudeai` or `console`.
+    #[serde(rename = "forceLoginMethod", default, skip_serializing_if = "Option::is_none")]
+    pub force_login_method: Option<String>,
+    #[serde(rename = "forceLoginOrgUUID", default, skip_serializing_if = "Option::is_none")]
+    pub force_login_org_uuid: Option<String>,
+    /// Hook event name (`PreToolUse`, `PostToolUse`, ...) to its matchers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<indexmap::IndexMap<String, Vec<HookMatcher>>>,
+    #[serde(rename = "includeCoAuthoredBy", default, skip_serializing_if = "Option::is_none")]
+    pub include_co_authored_by: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
-    pub max_tokens: Option<u32>,
-    pub temperature: Option<f64>,
-    pub top_p: Option<f64>,
+    #[serde(rename = "outputStyle", default, skip_serializing_if = "Option::is_none")]
+    pub output_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionsConfig>,
+    #[serde(rename = "statusLine", default, skip_serializing_if = "Option::is_none")]
+    pub status_line: Option<StatusLineConfig>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HookMatcher {
+    /// Tool name pattern; omitted for events that are not tied to a tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    pub hooks: Vec<HookCommand>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HookCommand {
+    /// Always `command` for now.
+    #[serde(rename = "type")]
+    pub hook_type: String,
+    pub command: String,
+    /// Seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
-    pub proxy_url: Option<String>,
-    pub verbose: Option<bool>,
-    pub stream: Option<bool>,
-    pub unsafe_html: Option<bool>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusLineConfig {
+    #[serde(rename = "type")]
+    pub status_type: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u32>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -190,10 +379,23 @@ pub struct EnvConfig {
     pub anthropic_base_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct PermissionsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub allow: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub deny: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ask: Option<Vec<String>>,
+    #[serde(rename = "defaultMode", default, skip_serializing_if = "Option::is_none")]
+    pub default_mode: Option<String>,
+    #[serde(rename = "additionalDirectories", default, skip_serializing_if = "Option::is_none")]
+    pub additional_directories: Option<Vec<String>>,
+    /// `disable` prevents `bypassPermissions` mode from being used.
+    #[serde(rename = "disableBypassPermissionsMode", default, skip_serializing_if = "Option::is_none")]
+    pub disable_bypass_permissions_mode: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -247,6 +449,33 @@ pub struct CustomCategory {
     pub updated_at: String,
 }
 
+/// A key value that was replaced by a rotation. Only the masked value is exposed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyHistoryEntry {
+    pub id: i64,
+    pub api_key_id: String,
+    pub masked_key: String,
+    /// `rotate` or `revert`.
+    pub reason: String,
+    pub rotated_at: String,
+    pub reverted_at: Option<String>,
+    /// Whether the key can still be restored with `revert_api_key`.
+    pub revertible: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyDriftReport {
+    pub settings_path: String,
+    /// One of `in_sync`, `drift`, `unknown_key` or `no_key`.
+    pub status: String,
+    pub disk_api_key: Option<String>,
+    pub disk_base_url: Option<String>,
+    pub matched_key_id: Option<String>,
+    pub matched_key_name: Option<String>,
+    pub base_url_matches: bool,
+    pub active_key_ids: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiKeyData {
     #[serde(rename = "ANTHROPIC_API_KEY")]
@@ -301,3 +530,91 @@ pub struct UpdateProjectRequest {
     pub project_type: Option<String>,
     pub description: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UPSTREAM: &str = include_str!("../tests/fixtures/router/ccr_upstream.json");
+    const LEGACY_SNAKE_CASE: &str = include_str!("../tests/fixtures/router/legacy_snake_case.json");
+    const LEGACY_TRANSFORMERS: &str = include_str!("../tests/fixtures/router/legacy_transformers.json");
+    const SETTINGS: &str = include_str!("../tests/fixtures/settings/settings.json");
+
+    fn round_trip(content: &str) -> (ClaudeCodeRouterConfig, serde_json::Value) {
+        let config = ClaudeCodeRouterConfig::from_json_str(content).unwrap();
+        let serialized = serde_json::to_value(&config).unwrap();
+        (config, serialized)
+    }
+
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        value.as_object().unwrap().keys().map(|k| k.as_str()).collect()
+    }
+
+    #[test]
+    fn upstream_config_round_trips_unchanged() {
+        let (config, serialized) = round_trip(UPSTREAM);
+        let original: serde_json::Value = serde_json::from_str(UPSTREAM).unwrap();
+
+        assert_eq!(config.providers.len(), 5);
+        assert_eq!(config.router.long_context.as_deref(), Some("openrouter,google/gemini-2.5-pro-preview"));
+        assert_eq!(config.transformers.as_ref().unwrap()[0].path.as_deref(), Some("/Users/xxx/.claude-code-router/plugins/gemini-cli.js"));
+        assert!(config.extra.contains_key("StatusLine"));
+
+        let modelscope = config.providers[4].transformer.as_ref().unwrap();
+        assert_eq!(modelscope.use_transformers[0], serde_json::json!(["maxtoken", { "max_tokens": 65536 }]));
+        assert!(modelscope.model_specific.contains_key("Qwen/Qwen3-235B-A22B-Thinking-2507"));
+
+        assert_eq!(serialized, original);
+        assert_eq!(serialized["Providers"][1]["transformer"], original["Providers"][1]["transformer"]);
+    }
+
+    #[test]
+    fn legacy_snake_case_config_is_written_in_upstream_shape() {
+        let (config, serialized) = round_trip(LEGACY_SNAKE_CASE);
+
+        assert_eq!(config.anthropic_api_key.as_deref(), Some("your-secret-key"));
+        assert_eq!(config.log, Some(true));
+        assert_eq!(config.router.long_context_threshold, Some(60000));
+        assert!(config.router.web_search.is_none());
+
+        assert_eq!(keys(&serialized), ["APIKEY", "PROXY_URL", "LOG", "API_TIMEOUT_MS", "Providers", "Router"]);
+        assert_eq!(keys(&serialized["Router"]), ["default", "think", "longContext", "longContextThreshold"]);
+        assert_eq!(serialized["Providers"][0]["transformer"], serde_json::json!({ "use": ["deepseek"] }));
+        assert_eq!(
+            serialized["Providers"][1]["transformer"],
+            serde_json::json!({ "use": [["maxtoken", { "max_tokens": 65536 }]] })
+        );
+
+        let (_, reparsed) = round_trip(&serialized.to_string());
+        assert_eq!(reparsed, serialized);
+    }
+
+    #[test]
+    fn legacy_transformer_entries_without_a_path_are_kept() {
+        let (config, serialized) = round_trip(LEGACY_TRANSFORMERS);
+        let transformers = config.transformers.as_ref().unwrap();
+
+        assert!(transformers[0].path.is_none());
+        assert_eq!(transformers[0].name.as_deref(), Some("maxtoken"));
+        assert_eq!(transformers[0].extra["args"], serde_json::json!({ "max_tokens": 16384 }));
+        assert_eq!(transformers[2].path.as_deref(), Some("/home/xxx/.claude-code-router/plugins/custom.js"));
+
+        let original: serde_json::Value = serde_json::from_str(LEGACY_TRANSFORMERS).unwrap();
+        assert_eq!(serialized["transformers"], original["transformers"]);
+        assert!(serialized["transformers"][1].get("path").is_none());
+    }
+
+    #[test]
+    fn claude_settings_round_trip_without_losing_unknown_keys() {
+        let original: serde_json::Value = serde_json::from_str(SETTINGS).unwrap();
+        let settings: ClaudeSettings = serde_json::from_value(original.clone()).unwrap();
+
+        assert!(settings.extra.contains_key("$schema"));
+        assert!(settings.permissions.as_ref().unwrap().extra.contains_key("experimentalFlag"));
+        let post_tool_use = &settings.hooks.as_ref().unwrap()["PostToolUse"][0];
+        assert!(post_tool_use.hooks[0].extra.contains_key("runInBackground"));
+        assert!(settings.status_line.as_ref().unwrap().extra.contains_key("refreshMs"));
+
+        assert_eq!(serde_json::to_value(&settings).unwrap(), original);
+    }
+}
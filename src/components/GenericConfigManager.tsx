@@ -128,11 +128,11 @@ export const GenericConfigManager = forwardRef<any, GenericConfigManagerProps>(
           const actualConfigPath = await invokeTauri<string>('get_config_path');
           const activeItem = newActiveItemId ? items.find(item => item.id === newActiveItemId) : null;
           if (activeItem) {
-            await configType.onConfigUpdate(activeItem.data, actualConfigPath);
+            await configType.onConfigUpdate(activeItem.data, actualConfigPath, activeItem.id);
             toast.success(`${configType.displayName}配置文件已更新`);
           } else if (newActiveItemId === null) {
             // If no active item, update with default data
-            await configType.onConfigUpdate(configType.defaultData, actualConfigPath);
+            await configType.onConfigUpdate(configType.defaultData, actualConfigPath, null);
             toast.success(`${configType.displayName}配置文件已重置为默认值`);
           }
         } catch (error) {
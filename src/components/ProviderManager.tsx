@@ -22,6 +22,7 @@ import {
 import { Textarea } from '@/components/ui/textarea';
 import { Plus, Edit2, Trash2, Server, Key, Eye, EyeOff, Copy, ChevronDown, ChevronRight, Globe, Settings } from 'lucide-react';
 import { toast } from 'sonner';
+import { invoke } from '@tauri-apps/api/core';
 
 import { Provider, PROVIDER_TEMPLATES, Transformer } from '@/types/claude-code-router';
 
@@ -34,6 +35,8 @@ export function ProviderManager({ providers, onChange }: ProviderManagerProps) {
   const [showDialog, setShowDialog] = useState(false);
   const [editingProvider, setEditingProvider] = useState<Provider | null>(null);
   const [showApiKeys, setShowApiKeys] = useState<Record<string, boolean>>({});
+  // 后端返回的是脱敏后的密钥，明文按需读取
+  const [revealedKeys, setRevealedKeys] = useState<Record<string, string>>({});
   const [expandedProviders, setExpandedProviders] = useState<Record<string, boolean>>({});
 
   const handleAdd = () => {
@@ -74,10 +77,23 @@ export function ProviderManager({ providers, onChange }: ProviderManagerProps) {
     toast.success(existingIndex >= 0 ? '提供商更新成功' : '提供商添加成功');
   };
 
-  const toggleApiKeyVisibility = (providerName: string) => {
+  // 尚未保存的提供商在后端没有记录，直接使用表单里填写的值
+  const revealApiKey = async (provider: Provider) => {
+    try {
+      return await invoke<string>('reveal_router_provider_key', { name: provider.name });
+    } catch {
+      return provider.api_key;
+    }
+  };
+
+  const toggleApiKeyVisibility = async (provider: Provider) => {
+    if (!showApiKeys[provider.name]) {
+      const apiKey = await revealApiKey(provider);
+      setRevealedKeys(prev => ({ ...prev, [provider.name]: apiKey }));
+    }
     setShowApiKeys(prev => ({
       ...prev,
-      [providerName]: !prev[providerName]
+      [provider.name]: !prev[provider.name]
     }));
   };
 
@@ -88,8 +104,8 @@ export function ProviderManager({ providers, onChange }: ProviderManagerProps) {
     }));
   };
 
-  const copyApiKey = (apiKey: string) => {
-    navigator.clipboard.writeText(apiKey);
+  const copyApiKey = async (provider: Provider) => {
+    navigator.clipboard.writeText(await revealApiKey(provider));
     toast.success('API Key 已复制到剪贴板');
   };
 
@@ -206,13 +222,13 @@ export function ProviderManager({ providers, onChange }: ProviderManagerProps) {
                     </div>
                     <div className="flex items-center gap-3">
                       <code className="flex-1 text-sm font-mono bg-white px-3 py-2 rounded border text-gray-800">
-                        {showApiKeys[provider.name] ? provider.api_key : maskApiKey(provider.api_key)}
+                        {showApiKeys[provider.name] ? (revealedKeys[provider.name] ?? provider.api_key) : maskApiKey(provider.api_key)}
                       </code>
                       <div className="flex gap-1">
                         <Button
                           variant="outline"
                           size="sm"
-                          onClick={() => toggleApiKeyVisibility(provider.name)}
+                          onClick={() => toggleApiKeyVisibility(provider)}
                           className="h-8 w-8 p-0 bg-white hover:bg-gray-50"
                         >
                           {showApiKeys[provider.name] ? 
@@ -223,7 +239,7 @@ export function ProviderManager({ providers, onChange }: ProviderManagerProps) {
                         <Button
                           variant="outline"
                           size="sm"
-                          onClick={() => copyApiKey(provider.api_key)}
+                          onClick={() => copyApiKey(provider)}
                           className="h-8 w-8 p-0 bg-white hover:bg-gray-50"
                         >
                           <Copy className="h-3 w-3" />
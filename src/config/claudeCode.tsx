@@ -130,6 +130,8 @@ export const claudeCodeConfigType: ConfigType<ApiKeyData> = {
   },
   listComponent: ({ item, isActive, onToggleActive, onEdit, onDelete }) => {
     const [showKey, setShowKey] = useState(false);
+    // 列表里只有脱敏后的密钥，明文按需向后端读取
+    const [revealedKey, setRevealedKey] = useState<string | null>(null);
     const resetTrigger = useGlobalReset();
     
     const handleToggleActive = async () => {
@@ -150,6 +152,7 @@ export const claudeCodeConfigType: ConfigType<ApiKeyData> = {
     useEffect(() => {
       if (resetTrigger > 0) { // 只有实际重置时才执行，避免初始化干扰
         setShowKey(false);
+        setRevealedKey(null);
       }
     }, [resetTrigger]);
     
@@ -161,22 +164,33 @@ export const claudeCodeConfigType: ConfigType<ApiKeyData> = {
       toast.success('已复制到剪贴板');
     };
 
-    const copyApiKey = () => {
-      if (item?.data?.ANTHROPIC_API_KEY) {
-        copyToClipboard(item.data.ANTHROPIC_API_KEY);
-      }
+    const revealApiKey = async () => {
+      const { invoke } = await import('@tauri-apps/api/core');
+      return invoke<string>('reveal_api_key', { id: item.id });
     };
 
-    const maskApiKey = (key: string) => {
-      if (!key || key.length <= 8) return key || '';
-      const start = key.slice(0, 4);
-      const end = key.slice(-4);
-      const middle = '*'.repeat(Math.min(key.length - 8, 20));
-      return `${start}${middle}${end}`;
+    const copyApiKey = async () => {
+      try {
+        copyToClipboard(await revealApiKey());
+      } catch (error) {
+        toast.error('读取密钥失败');
+        console.error('Failed to reveal API key:', error);
+      }
     };
 
-    const toggleKeyVisibility = () => {
-      setShowKey(!showKey);
+    const toggleKeyVisibility = async () => {
+      if (showKey) {
+        setShowKey(false);
+        setRevealedKey(null);
+        return;
+      }
+      try {
+        setRevealedKey(await revealApiKey());
+        setShowKey(true);
+      } catch (error) {
+        toast.error('读取密钥失败');
+        console.error('Failed to reveal API key:', error);
+      }
     };
 
     return (
@@ -194,7 +208,7 @@ export const claudeCodeConfigType: ConfigType<ApiKeyData> = {
             <div className="text-xs text-muted-foreground mb-1">ANTHROPIC_API_KEY:</div>
             <code className="text-xs bg-muted px-2 py-1 rounded font-mono block break-all whitespace-pre-wrap">
               {item?.data?.ANTHROPIC_API_KEY ? 
-                (showKey ? item.data.ANTHROPIC_API_KEY : maskApiKey(item.data.ANTHROPIC_API_KEY)) : 
+                (showKey && revealedKey ? revealedKey : item.data.ANTHROPIC_API_KEY) : 
                 '未设置'
               }
             </code>
@@ -266,17 +280,16 @@ export const claudeCodeConfigType: ConfigType<ApiKeyData> = {
     delete: "delete_api_key",
   },
   configPath: "~/.claude/settings.json",
-  onConfigUpdate: async (data: ApiKeyData, configPath: string) => {
+  onConfigUpdate: async (_data: ApiKeyData, configPath: string, id: string | null) => {
     try {
       // Only invoke Tauri command if running in Tauri environment
       const { isTauri } = await import('@tauri-apps/api/core');
       if (await isTauri()) {
         const { invoke } = await import('@tauri-apps/api/core');
-        const { ANTHROPIC_API_KEY, ANTHROPIC_BASE_URL } = data;
+        // 只传密钥 id，由后端读取并解密
         await invoke('update_config_env', { 
           configPath, 
-          apiKey: ANTHROPIC_API_KEY, 
-          baseUrl: ANTHROPIC_BASE_URL
+          apiKeyId: id
         });
       }
     } catch (error) {
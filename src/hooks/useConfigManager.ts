@@ -46,61 +46,24 @@ export function useConfigManager<T = any>(configType: ConfigType<T>) {
     try {
       console.log('Starting syncActiveConfigWithFile...');
       console.log('Current activeItemId:', activeItemId);
-      console.log('Config items:', configItems.map((item: any) => ({ id: item.id, name: item.name, key: item.data.ANTHROPIC_API_KEY?.slice(0, 10) + '...' })));
+      console.log('Config items:', configItems.map((item: any) => ({ id: item.id, name: item.name })));
       
-      // 读取实际配置文件内容
-      const configPath = await invokeTauri<string>('get_config_path');
-      console.log('Config path:', configPath);
+      // 列表里的密钥已脱敏，由后端比对配置文件里的密钥并返回匹配的 id
+      const report = await invokeTauri<{ status: string; matched_key_id: string | null }>('detect_api_key_drift');
+      console.log('Drift report status:', report.status);
       
-      const fileContent = await invokeTauri<string>('read_config_file', { configPath });
-      console.log('File content length:', fileContent.length);
+      const matchingItem = report.matched_key_id
+        ? configItems.find((item: any) => item.id === report.matched_key_id)
+        : undefined;
       
-      if (!fileContent || fileContent.trim() === '') {
-        console.log('Config file is empty or not found');
-        return;
-      }
-      
-      const configData = JSON.parse(fileContent);
-      console.log('Parsed config data keys:', Object.keys(configData));
-      console.log('Full config data:', configData);
+      console.log('Matching item found:', matchingItem ? { id: matchingItem.id, name: matchingItem.name } : 'none');
       
-      // 查找API密钥，可能在不同的位置
-      let actualApiKey = null;
-      
-      // 检查根级别
-      if (configData.ANTHROPIC_API_KEY) {
-        actualApiKey = configData.ANTHROPIC_API_KEY;
-        console.log('Found API key at root level');
-      }
-      // 检查env对象中
-      else if (configData.env && configData.env.ANTHROPIC_API_KEY) {
-        actualApiKey = configData.env.ANTHROPIC_API_KEY;
-        console.log('Found API key in env object');
-      }
-      // 检查其他可能的位置
-      else if (configData.apiKeyHelper && configData.apiKeyHelper.ANTHROPIC_API_KEY) {
-        actualApiKey = configData.apiKeyHelper.ANTHROPIC_API_KEY;
-        console.log('Found API key in apiKeyHelper object');
-      }
-      
-      console.log('Actual API key from file:', actualApiKey?.slice(0, 10) + '...');
-      
-      if (actualApiKey) {
-        const matchingItem = configItems.find((item: any) => 
-          item.data.ANTHROPIC_API_KEY === actualApiKey
-        );
-        
-        console.log('Matching item found:', matchingItem ? { id: matchingItem.id, name: matchingItem.name } : 'none');
-        
-        if (matchingItem && matchingItem.id !== activeItemId) {
-          console.log('Config file mismatch detected, syncing active item from', activeItemId, 'to', matchingItem.id);
-          setActiveItemId(matchingItem.id);
-          localStorage.setItem(`active_${configType.id}`, matchingItem.id);
-        } else {
-          console.log('No sync needed - either no match or already correct');
-        }
+      if (matchingItem && matchingItem.id !== activeItemId) {
+        console.log('Config file mismatch detected, syncing active item from', activeItemId, 'to', matchingItem.id);
+        setActiveItemId(matchingItem.id);
+        localStorage.setItem(`active_${configType.id}`, matchingItem.id);
       } else {
-        console.log('No ANTHROPIC_API_KEY found in config file');
+        console.log('No sync needed - either no match or already correct');
       }
     } catch (error) {
       console.warn('Failed to sync active config with file:', error);
@@ -223,7 +186,7 @@ export function useConfigManager<T = any>(configType: ConfigType<T>) {
         try {
           // Get the actual config path from the backend
           const actualConfigPath = await invokeTauri<string>('get_config_path');
-          await configType.onConfigUpdate(result.data, actualConfigPath);
+          await configType.onConfigUpdate(result.data, actualConfigPath, result.id);
           toast.success(`${configType.displayName}配置文件已更新`);
         } catch (error) {
           console.error("Failed to update config file:", error);
@@ -259,13 +222,15 @@ export function useConfigManager<T = any>(configType: ConfigType<T>) {
       let transformedRequest = request;
       if (configType.id === 'claude-code' && request.data) {
         const apiKeyData = request.data as any;
+        // 未修改的密钥字段里是脱敏值，不提交，避免被当成新密钥
+        const previousKey = (items.find(item => item.id === id)?.data as any)?.ANTHROPIC_API_KEY;
         transformedRequest = {
           name: request.name,
           description: request.description,
-          ANTHROPIC_API_KEY: apiKeyData.ANTHROPIC_API_KEY,
+          ANTHROPIC_API_KEY: apiKeyData.ANTHROPIC_API_KEY === previousKey ? undefined : apiKeyData.ANTHROPIC_API_KEY,
           ANTHROPIC_BASE_URL: apiKeyData.ANTHROPIC_BASE_URL || undefined, // Convert empty string to undefined
         } as any;
-        console.log('Transformed claude-code request:', transformedRequest);
+        console.log('Transformed claude-code request:', { ...transformedRequest, ANTHROPIC_API_KEY: undefined });
       }
       
       const result = await invokeTauri<ConfigItem<T>>(configType.apiEndpoints.update, { id, request: transformedRequest });
@@ -290,7 +255,7 @@ export function useConfigManager<T = any>(configType: ConfigType<T>) {
         try {
           // Get the actual config path from the backend
           const actualConfigPath = await invokeTauri<string>('get_config_path');
-          await configType.onConfigUpdate(result.data, actualConfigPath);
+          await configType.onConfigUpdate(result.data, actualConfigPath, result.id);
           toast.success(`${configType.displayName}配置文件已更新`);
         } catch (error) {
           console.error("Failed to update config file:", error);
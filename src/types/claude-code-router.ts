@@ -1,7 +1,7 @@
 // Claude Code Router 配置数据结构
 
 export interface Transformer {
-  use: (string | [string, Record<string, any>])[];
+  use?: (string | [string, Record<string, any>])[];
   [modelName: string]: {
     use: (string | [string, Record<string, any>])[];
   } | any;
@@ -11,6 +11,8 @@ export interface Provider {
   name: string;
   api_base_url: string;
   api_key: string;
+  // 引用已保存的 API 密钥，导出 config.json 时才替换为真实密钥
+  api_key_id?: string;
   models: string[];
   transformer?: Transformer;
 }
@@ -25,13 +27,17 @@ export interface RouterConfig {
 }
 
 export interface CustomTransformer {
-  path: string;
+  // 供应商的 use 链通过 name 引用转换器
+  name?: string;
+  // 旧版本写出的条目只有 name 和 args，没有 path
+  path?: string;
   options?: Record<string, any>;
+  [key: string]: any;
 }
 
 export interface ClaudeCodeRouterConfig {
   // 全局配置
-  ANTHROPIC_API_KEY?: string;
+  APIKEY?: string;
   PROXY_URL?: string;
   LOG?: boolean;
   HOST?: string;
@@ -47,6 +53,9 @@ export interface ClaudeCodeRouterConfig {
   
   // 自定义转换器
   transformers?: CustomTransformer[];
+
+  // 其他 CCR 配置项（如 StatusLine），原样保留
+  [key: string]: any;
 }
 
 // 内置转换器列表
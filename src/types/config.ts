@@ -49,7 +49,8 @@ export interface ConfigType<T = any> {
     setActive?: string;
   };
   configPath?: string;
-  onConfigUpdate?: (data: T, configPath: string) => Promise<void>;
+  // id 为空表示没有启用的配置项
+  onConfigUpdate?: (data: T, configPath: string, id: string | null) => Promise<void>;
 }
 
 export interface BackupFile {